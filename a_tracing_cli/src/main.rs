@@ -0,0 +1,237 @@
+use std::{
+    error::Error,
+    path::PathBuf,
+    process::ExitCode,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use a_tracing_lib::{
+    color::display::{DisplayTransform, ToneMapping},
+    image::ImageFormat,
+    sampling::{
+        HaltonSampler, JitteredSampler, MultiJitteredSampler, RandomSampler, RegularSampler,
+        SobolSampler,
+    },
+    tracer::{aov::Aov, ATracer, RenderMode, RenderStatus},
+};
+use clap::{Parser, ValueEnum};
+
+/// How often the progress is printed while rendering
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Render a scene file without opening a window
+#[derive(Parser)]
+#[command(version)]
+struct Arguments {
+    /// Scene file to render
+    scene: PathBuf,
+
+    /// Path of the rendered image, the extension picks the format: png, ppm, hdr or exr
+    #[arg(short, long, default_value = "render.png")]
+    output: PathBuf,
+
+    /// Resolution as WIDTHxHEIGHT, defaults to the resolution stored in the scene file
+    #[arg(short, long, value_parser = parse_resolution)]
+    resolution: Option<(u32, u32)>,
+
+    /// Samples per pixel, defaults to the number stored in the scene file
+    #[arg(short, long)]
+    samples: Option<u32>,
+
+    /// Sampler distributing the samples over each pixel, defaults to the sampler stored in the scene file
+    #[arg(long, value_enum)]
+    sampler: Option<Sampler>,
+
+    /// Seed of the sampler picked with --sampler, renders with the same seed are identical
+    #[arg(long, default_value_t = 0)]
+    seed: u32,
+
+    /// Extra output rendered next to the colors and saved in exr images, can be repeated:
+    /// depth, normal, albedo, position, object_id or sample_count
+    #[arg(long = "aov", value_parser = parse_aov)]
+    aovs: Vec<Aov>,
+
+    /// Exposure in stops applied before tone mapping png and ppm images
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Tone mapping operator of png and ppm images
+    #[arg(long, value_enum, default_value = "none")]
+    tone_mapping: ToneMappingChoice,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Sampler {
+    /// Samples on a regular grid
+    Regular,
+    /// Uniform random samples
+    Random,
+    /// One random sample in every cell of a grid
+    Jittered,
+    /// Correlated multi-jittered samples
+    MultiJittered,
+    /// Halton low-discrepancy sequence
+    Halton,
+    /// Owen scrambled Sobol low-discrepancy sequence
+    Sobol,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ToneMappingChoice {
+    /// Clip the colors
+    None,
+    /// Reinhard operator
+    Reinhard,
+    /// ACES filmic curve
+    Aces,
+    /// AgX curve
+    Agx,
+}
+
+impl From<ToneMappingChoice> for ToneMapping {
+    fn from(value: ToneMappingChoice) -> Self {
+        match value {
+            ToneMappingChoice::None => ToneMapping::None,
+            ToneMappingChoice::Reinhard => ToneMapping::Reinhard,
+            ToneMappingChoice::Aces => ToneMapping::Aces,
+            ToneMappingChoice::Agx => ToneMapping::AgX,
+        }
+    }
+}
+
+fn parse_resolution(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| "expected WIDTHxHEIGHT".to_owned())?;
+    let width: u32 = width
+        .parse()
+        .map_err(|_| format!("invalid width '{width}'"))?;
+    let height: u32 = height
+        .parse()
+        .map_err(|_| format!("invalid height '{height}'"))?;
+
+    if width < 2 || height < 2 {
+        return Err("resolution has to be at least 2x2".to_owned());
+    }
+
+    Ok((width, height))
+}
+
+fn parse_aov(value: &str) -> Result<Aov, String> {
+    Aov::from_name(value).ok_or_else(|| format!("unknown AOV '{value}'"))
+}
+
+fn main() -> ExitCode {
+    let arguments = Arguments::parse();
+
+    match render(&arguments) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn render(arguments: &Arguments) -> Result<(), Box<dyn Error>> {
+    // Check the format before rendering instead of failing once the render is done
+    let format = ImageFormat::from_path(&arguments.output).ok_or_else(|| {
+        format!(
+            "{}: unknown image format, use png, ppm, hdr or exr",
+            arguments.output.display()
+        )
+    })?;
+
+    let mut tracer = ATracer::new(2, 2);
+    tracer.load_scene(&arguments.scene)?;
+
+    if let Some((width, height)) = arguments.resolution {
+        tracer.resize(width, height);
+    }
+
+    let settings = tracer.render_settings_mut();
+
+    if let Some(samples) = arguments.samples {
+        settings.number_of_samples = samples.max(1);
+    }
+
+    if let Some(sampler) = arguments.sampler {
+        let samples_per_side = ((settings.number_of_samples as f32).sqrt().ceil() as u32).max(1);
+        settings.sampler = match sampler {
+            Sampler::Regular => Arc::new(RegularSampler::new(samples_per_side)),
+            Sampler::Random => Arc::new(RandomSampler::new(arguments.seed)),
+            Sampler::Jittered => Arc::new(JitteredSampler::new(samples_per_side, arguments.seed)),
+            Sampler::MultiJittered => {
+                Arc::new(MultiJitteredSampler::new(samples_per_side, arguments.seed))
+            }
+            Sampler::Halton => Arc::new(HaltonSampler::new(arguments.seed)),
+            Sampler::Sobol => Arc::new(SobolSampler::new(arguments.seed)),
+        };
+    }
+
+    settings.aovs.extend(&arguments.aovs);
+
+    // Without a limit a progressive render would never finish, so stop at the sample count
+    if let RenderMode::Progressive {
+        max_samples: max_samples @ None,
+        time_budget: None,
+    } = &mut settings.render_mode
+    {
+        *max_samples = Some(settings.number_of_samples);
+    }
+
+    let resolution = settings.resolution;
+    eprintln!(
+        "Rendering {} at {}x{}",
+        arguments.scene.display(),
+        resolution.width,
+        resolution.height
+    );
+
+    // The tracer only starts a render once the quick render made it ready
+    tracer.update();
+    tracer.start_render();
+
+    let started = Instant::now();
+    while tracer.render_status() == RenderStatus::Rendering {
+        thread::sleep(PROGRESS_INTERVAL);
+        tracer.update();
+        print_progress(&tracer, started);
+    }
+    eprintln!();
+
+    if tracer.render_status() != RenderStatus::Finished {
+        return Err("render was canceled".into());
+    }
+
+    tracer.set_display_transform(DisplayTransform::new(
+        arguments.exposure,
+        arguments.tone_mapping.into(),
+    ));
+    tracer.save_image(&arguments.output, Some(format))?;
+    eprintln!(
+        "Wrote {} after {:.1}s",
+        arguments.output.display(),
+        started.elapsed().as_secs_f32()
+    );
+
+    Ok(())
+}
+
+fn print_progress(tracer: &ATracer, started: Instant) {
+    let elapsed = started.elapsed().as_secs_f32();
+
+    match tracer.render_settings().render_mode {
+        RenderMode::Full => eprint!(
+            "\r{:5.1}% after {elapsed:.1}s",
+            tracer.render_progress() * 100.0
+        ),
+        RenderMode::Progressive { .. } => eprint!(
+            "\r{:5.1}%, {} samples per pixel after {elapsed:.1}s",
+            tracer.render_progress() * 100.0,
+            tracer.completed_passes()
+        ),
+    }
+}
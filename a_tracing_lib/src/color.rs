@@ -1,7 +1,12 @@
+pub mod display;
+
 use glam::Vec3;
+use serde::{Deserialize, Serialize};
 
 /// Struct holding a color in rgb format using f32's
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// Stored as an (r, g, b) tuple in scene files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f32; 3]", into = "[f32; 3]")]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -27,8 +32,57 @@ impl Color {
     pub fn lerp(self, rhs: Self, t: f32) -> Self {
         self + ((rhs - self) * t)
     }
+
+    pub fn max_component(self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    /// Perceived brightness of a linear Rec. 709 color
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Convert an 8 bit per channel sRGB encoded color to linear rgb
+    pub fn from_srgb8(r: u8, g: u8, b: u8) -> Self {
+        Color::new(
+            srgb_to_linear(r as f32 / 255.0),
+            srgb_to_linear(g as f32 / 255.0),
+            srgb_to_linear(b as f32 / 255.0),
+        )
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from(value: [f32; 3]) -> Self {
+        Color::new(value[0], value[1], value[2])
+    }
+}
+
+impl From<Color> for [f32; 3] {
+    fn from(value: Color) -> Self {
+        [value.r, value.g, value.b]
+    }
+}
+
+/// Decode a single sRGB encoded channel in range [0, 1] to linear
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a single linear channel in range [0, 1] with the sRGB transfer function
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
 }
 
+/// Clamps the linear values without encoding them, the display transform gives the colors shown on screen
 impl From<Color> for [u8; 4] {
     fn from(value: Color) -> Self {
         let r = (value.r.clamp(0.0, 1.0) * 255.0) as u8;
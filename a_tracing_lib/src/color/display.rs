@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use super::{linear_to_srgb, Color};
+
+/// Conversion of the linear colors of the color buffer to the 8-bit sRGB colors shown on screen
+/// Used by ```draw``` and when saving PNG and PPM images, HDR and EXR images keep the linear colors
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DisplayTransform {
+    /// Exposure in stops, every stop doubles the brightness before tone mapping
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tone_mapping: ToneMapping::None,
+        }
+    }
+}
+
+impl DisplayTransform {
+    pub fn new(exposure: f32, tone_mapping: ToneMapping) -> Self {
+        Self {
+            exposure,
+            tone_mapping,
+        }
+    }
+
+    /// Apply the exposure and tone mapping, the result is linear with every channel in range [0, 1]
+    pub fn tone_map(&self, color: Color) -> Color {
+        let exposed = color * 2f32.powf(self.exposure);
+        let exposed = Color::new(exposed.r.max(0.0), exposed.g.max(0.0), exposed.b.max(0.0));
+        let mapped = self.tone_mapping.apply(exposed);
+
+        Color::new(
+            mapped.r.clamp(0.0, 1.0),
+            mapped.g.clamp(0.0, 1.0),
+            mapped.b.clamp(0.0, 1.0),
+        )
+    }
+
+    /// Tone map the color and encode it with the sRGB transfer function as 8-bit RGBA
+    pub fn to_srgb8(&self, color: Color) -> [u8; 4] {
+        let mapped = self.tone_map(color);
+        let encode = |value: f32| (linear_to_srgb(value) * 255.0).round() as u8;
+
+        [encode(mapped.r), encode(mapped.g), encode(mapped.b), 255]
+    }
+}
+
+/// Operator compressing the unbounded range of the rendered colors into the displayable range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToneMapping {
+    /// Clip every channel to 1
+    None,
+    /// Per channel x / (1 + x), never clips but desaturates and flattens the highlights
+    Reinhard,
+    /// Fit of the ACES reference rendering and sRGB output transform by Stephen Hill
+    Aces,
+    /// Fit of Troy Sobotka's AgX, desaturates bright colors towards white instead of skewing their hue
+    AgX,
+}
+
+impl ToneMapping {
+    pub const ALL: [ToneMapping; 4] = [
+        ToneMapping::None,
+        ToneMapping::Reinhard,
+        ToneMapping::Aces,
+        ToneMapping::AgX,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMapping::None => "none",
+            ToneMapping::Reinhard => "reinhard",
+            ToneMapping::Aces => "aces",
+            ToneMapping::AgX => "agx",
+        }
+    }
+
+    /// Map a non-negative linear color, the result may slightly exceed [0, 1]
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            ToneMapping::None => color,
+            ToneMapping::Reinhard => Color::new(
+                color.r / (1.0 + color.r),
+                color.g / (1.0 + color.g),
+                color.b / (1.0 + color.b),
+            ),
+            ToneMapping::Aces => aces(color),
+            ToneMapping::AgX => agx(color),
+        }
+    }
+}
+
+/// Multiply a color by a 3x3 matrix given in row-major order
+fn transform(matrix: [[f32; 3]; 3], color: Color) -> Color {
+    let row = |row: [f32; 3]| row[0] * color.r + row[1] * color.g + row[2] * color.b;
+    Color::new(row(matrix[0]), row(matrix[1]), row(matrix[2]))
+}
+
+fn map_channels(color: Color, f: impl Fn(f32) -> f32) -> Color {
+    Color::new(f(color.r), f(color.g), f(color.b))
+}
+
+/// sRGB to the ACES rendering space, including the exposure bias of the reference transform
+const ACES_INPUT: [[f32; 3]; 3] = [
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+];
+
+/// ACES output space back to sRGB
+const ACES_OUTPUT: [[f32; 3]; 3] = [
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+];
+
+fn aces(color: Color) -> Color {
+    let color = transform(ACES_INPUT, color);
+    let color = map_channels(color, |x| {
+        (x * (x + 0.0245786) - 0.000090537) / (x * (0.983729 * x + 0.432951) + 0.238081)
+    });
+    transform(ACES_OUTPUT, color)
+}
+
+/// sRGB to the AgX working space, pulling the primaries inwards
+const AGX_INSET: [[f32; 3]; 3] = [
+    [0.842479, 0.078434, 0.079224],
+    [0.042328, 0.878469, 0.079166],
+    [0.042376, 0.078434, 0.879143],
+];
+
+/// Inverse of the inset matrix
+const AGX_OUTSET: [[f32; 3]; 3] = [
+    [1.196879, -0.098021, -0.099030],
+    [-0.052897, 1.151903, -0.098961],
+    [-0.052972, -0.098043, 1.151074],
+];
+
+/// Range of the log encoding in stops around middle grey
+const AGX_MIN_EV: f32 = -12.47393;
+const AGX_MAX_EV: f32 = 4.026069;
+
+fn agx(color: Color) -> Color {
+    let color = transform(AGX_INSET, color);
+    let color = map_channels(color, |x| {
+        let encoded = x.max(1e-10).log2().clamp(AGX_MIN_EV, AGX_MAX_EV);
+        let x = (encoded - AGX_MIN_EV) / (AGX_MAX_EV - AGX_MIN_EV);
+
+        // Polynomial fit of the default AgX sigmoid
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    });
+    let color = transform(AGX_OUTSET, color);
+
+    // The sigmoid outputs display encoded values, decode them so the sRGB encoding can be applied afterwards
+    map_channels(color, |x| x.max(0.0).powf(2.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::{DisplayTransform, ToneMapping};
+
+    #[test]
+    fn srgb_encoding() {
+        let transform = DisplayTransform::default();
+
+        assert_eq!(
+            transform.to_srgb8(Color::new(0.0, 1.0, 5.0)),
+            [0, 255, 255, 255]
+        );
+        // Linear middle grey is encoded well above half
+        assert_eq!(transform.to_srgb8(Color::new(0.18, 0.18, 0.18))[0], 118);
+        assert_eq!(transform.to_srgb8(Color::new(-1.0, 0.0, 0.0))[0], 0);
+    }
+
+    #[test]
+    fn exposure_scales_colors() {
+        let transform = DisplayTransform::new(1.0, ToneMapping::None);
+        let mapped = transform.tone_map(Color::new(0.25, 0.1, 0.0));
+
+        assert!((mapped.r - 0.5).abs() < 1e-6);
+        assert!((mapped.g - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn operators_compress_highlights() {
+        for tone_mapping in ToneMapping::ALL {
+            let transform = DisplayTransform::new(0.0, tone_mapping);
+            let black = transform.tone_map(Color::default());
+            let grey = transform.tone_map(Color::new(0.18, 0.18, 0.18));
+            let bright = transform.tone_map(Color::new(4.0, 4.0, 4.0));
+            let brighter = transform.tone_map(Color::new(16.0, 16.0, 16.0));
+
+            assert!(black.max_component() < 0.01, "{tone_mapping:?}");
+            assert!(grey.r > black.r && bright.r > grey.r, "{tone_mapping:?}");
+            assert!(
+                brighter.r >= bright.r && brighter.r <= 1.0,
+                "{tone_mapping:?}"
+            );
+
+            if tone_mapping != ToneMapping::None {
+                assert!(bright.r < 1.0, "{tone_mapping:?}");
+            }
+        }
+    }
+}
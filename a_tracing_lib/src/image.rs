@@ -0,0 +1,562 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::color::Color;
+
+/// File format of a saved image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// 8-bit PNG
+    Png,
+    /// 8-bit binary PPM
+    Ppm,
+    /// 32-bit float Radiance RGBE storing the linear colors
+    Hdr,
+    /// 32-bit float OpenEXR storing the linear colors and every AOV buffer in named channels
+    Exr,
+}
+
+impl ImageFormat {
+    /// Pick the format matching the extension of the path, ignoring case
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            "hdr" => Some(ImageFormat::Hdr),
+            "exr" => Some(ImageFormat::Exr),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when saving or reading an image fails
+#[derive(Debug)]
+pub enum ImageError {
+    /// The file couldn't be written
+    Io { path: PathBuf, source: io::Error },
+    /// Encoding the PNG image failed
+    Png {
+        path: PathBuf,
+        source: png::EncodingError,
+    },
+    /// Encoding the OpenEXR image failed
+    Exr {
+        path: PathBuf,
+        source: exr::error::Error,
+    },
+    /// No format was given and the extension of the path doesn't name one
+    UnknownFormat { path: PathBuf },
+    /// The file isn't a valid image of the expected format
+    Decode { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            ImageError::Png { path, source } => {
+                write!(f, "{}: encoding PNG failed: {source}", path.display())
+            }
+            ImageError::Exr { path, source } => {
+                write!(f, "{}: encoding OpenEXR failed: {source}", path.display())
+            }
+            ImageError::UnknownFormat { path } => {
+                write!(f, "{}: unknown image format", path.display())
+            }
+            ImageError::Decode { path, message } => {
+                write!(f, "{}: decoding failed: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io { source, .. } => Some(source),
+            ImageError::Png { source, .. } => Some(source),
+            ImageError::Exr { source, .. } => Some(source),
+            ImageError::UnknownFormat { .. } | ImageError::Decode { .. } => None,
+        }
+    }
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, ImageError> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(io_error(path))
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> ImageError + '_ {
+    |source| ImageError::Io {
+        path: path.to_owned(),
+        source,
+    }
+}
+
+/// Write RGBA pixels in row-major order from the top row down as an 8-bit PNG, the alpha channel is dropped
+pub fn write_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[[u8; 4]],
+) -> Result<(), ImageError> {
+    let png_error = |source| ImageError::Png {
+        path: path.to_owned(),
+        source,
+    };
+
+    let mut encoder = png::Encoder::new(create_file(path)?, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data: Vec<u8> = pixels
+        .iter()
+        .flat_map(|pixel| &pixel[..3])
+        .copied()
+        .collect();
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&data).map_err(png_error)?;
+    writer.finish().map_err(png_error)
+}
+
+/// Write RGBA pixels in row-major order from the top row down as a binary PPM, the alpha channel is dropped
+pub fn write_ppm(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[[u8; 4]],
+) -> Result<(), ImageError> {
+    let mut writer = create_file(path)?;
+
+    write!(writer, "P6\n{width} {height}\n255\n").map_err(io_error(path))?;
+    for pixel in pixels {
+        writer.write_all(&pixel[..3]).map_err(io_error(path))?;
+    }
+
+    writer.flush().map_err(io_error(path))
+}
+
+/// Write linear colors in row-major order from the top row down as an uncompressed Radiance RGBE image
+pub fn write_hdr(path: &Path, width: u32, height: u32, colors: &[Color]) -> Result<(), ImageError> {
+    let mut writer = create_file(path)?;
+
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {height} +X {width}\n"
+    )
+    .map_err(io_error(path))?;
+    for &color in colors {
+        writer
+            .write_all(&color_to_rgbe(color))
+            .map_err(io_error(path))?;
+    }
+
+    writer.flush().map_err(io_error(path))
+}
+
+/// RGBA pixels of an 8-bit image read from a file, in row-major order from the top row down
+pub struct PngImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// Read a PNG image, grayscale and palette images are expanded to RGBA and 16-bit channels are reduced to 8 bits
+pub fn read_png(path: &Path) -> Result<PngImage, ImageError> {
+    let decode_error = |source: png::DecodingError| ImageError::Decode {
+        path: path.to_owned(),
+        message: source.to_string(),
+    };
+
+    let file = File::open(path).map_err(io_error(path))?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(decode_error)?;
+
+    let mut data = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let info = reader.next_frame(&mut data).map_err(decode_error)?;
+    let data = &data[..info.buffer_size()];
+
+    let pixels = match info.color_type {
+        png::ColorType::Grayscale => data.iter().map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        png::ColorType::Rgba => data
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect(),
+        png::ColorType::Indexed => {
+            return Err(ImageError::Decode {
+                path: path.to_owned(),
+                message: "palette wasn't expanded".to_owned(),
+            })
+        }
+    };
+
+    Ok(PngImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+/// Linear colors of an image read from a file, in row-major order from the top row down
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    pub colors: Vec<Color>,
+}
+
+/// Read a Radiance RGBE image, either uncompressed or run-length encoded
+/// Only the standard orientation with rows from the top down is supported
+pub fn read_hdr(path: &Path) -> Result<HdrImage, ImageError> {
+    let bytes = fs::read(path).map_err(io_error(path))?;
+
+    decode_hdr(&bytes).map_err(|message| ImageError::Decode {
+        path: path.to_owned(),
+        message,
+    })
+}
+
+/// Largest number of pixels of a Radiance HDR image that is read, 32768 by 16384
+const MAX_HDR_PIXELS: usize = 1 << 29;
+
+fn decode_hdr(bytes: &[u8]) -> Result<HdrImage, String> {
+    let mut lines = bytes.split(|&byte| byte == b'\n');
+    let mut position = 0;
+    let mut next_line = || {
+        let line = lines.next()?;
+        position += line.len() + 1;
+        Some(String::from_utf8_lossy(line).into_owned())
+    };
+
+    let magic = next_line().unwrap_or_default();
+    if !magic.starts_with("#?") {
+        return Err("missing the Radiance header".to_owned());
+    }
+
+    // The header ends with an empty line
+    loop {
+        let line = next_line().ok_or("unexpected end of the header")?;
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format.trim() != "32-bit_rle_rgbe" {
+                return Err(format!("unsupported pixel format {format}"));
+            }
+        }
+    }
+
+    let resolution = next_line().ok_or("missing the resolution")?;
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse::<u32>(), width.parse::<u32>()),
+        _ => return Err(format!("unsupported orientation {resolution}")),
+    };
+    let (Ok(height), Ok(width)) = (height, width) else {
+        return Err(format!("invalid resolution {resolution}"));
+    };
+    let pixel_count = (width as usize)
+        .checked_mul(height as usize)
+        .filter(|&count| count > 0 && count <= MAX_HDR_PIXELS)
+        .ok_or_else(|| format!("unsupported resolution {resolution}"))?;
+
+    // Run-length encoding can store more pixels than bytes, so a short file doesn't reserve the full image up front
+    let mut data = bytes.get(position..).unwrap_or_default();
+    let mut colors = Vec::with_capacity(pixel_count.min(data.len()));
+    let mut scanline = vec![[0u8; 4]; width as usize];
+
+    for _ in 0..height {
+        data = read_scanline(data, &mut scanline)?;
+        colors.extend(scanline.iter().map(|&rgbe| rgbe_to_color(rgbe)));
+    }
+
+    Ok(HdrImage {
+        width,
+        height,
+        colors,
+    })
+}
+
+/// Read a single row of pixels and return the data following it
+fn read_scanline<'a>(data: &'a [u8], scanline: &mut [[u8; 4]]) -> Result<&'a [u8], String> {
+    let width = scanline.len();
+    let truncated = || "unexpected end of the pixel data".to_owned();
+
+    // Run-length encoded rows start with 2, 2 and the width, then store every channel separately
+    let is_run_length_encoded = (8..0x8000).contains(&width)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && usize::from(data[2]) << 8 | usize::from(data[3]) == width;
+
+    if !is_run_length_encoded {
+        let bytes = data.get(..width * 4).ok_or_else(truncated)?;
+        for (pixel, rgbe) in scanline.iter_mut().zip(bytes.chunks_exact(4)) {
+            pixel.copy_from_slice(rgbe);
+        }
+        return Ok(&data[width * 4..]);
+    }
+
+    let mut data = &data[4..];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, rest) = data.split_first().ok_or_else(truncated)?;
+            let count = usize::from(count);
+
+            if count > 128 {
+                let run = count - 128;
+                let (&value, rest) = rest.split_first().ok_or_else(truncated)?;
+                let pixels = scanline.get_mut(x..x + run).ok_or("run exceeds the row")?;
+                pixels.iter_mut().for_each(|pixel| pixel[channel] = value);
+                data = rest;
+                x += run;
+            } else {
+                if count == 0 {
+                    return Err("empty run".to_owned());
+                }
+
+                let values = rest.get(..count).ok_or_else(truncated)?;
+                let pixels = scanline
+                    .get_mut(x..x + count)
+                    .ok_or("run exceeds the row")?;
+                for (pixel, &value) in pixels.iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                data = &rest[count..];
+                x += count;
+            }
+        }
+    }
+
+    Ok(data)
+}
+
+/// Named float channel of an image, one value per pixel in row-major order from the top row down
+pub struct ImageChannel {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+/// Write the channels as 32-bit floats to a single part OpenEXR image without clamping
+/// Channels of the same layer share a prefix separated by a dot, such as depth.Z
+pub fn write_exr(
+    path: &Path,
+    width: u32,
+    height: u32,
+    channels: Vec<ImageChannel>,
+) -> Result<(), ImageError> {
+    use exr::prelude::{AnyChannel, AnyChannels, FlatSamples, Image, SmallVec, WritableImage};
+
+    let channels = channels
+        .into_iter()
+        .map(|channel| AnyChannel::new(channel.name.as_str(), FlatSamples::F32(channel.values)))
+        .collect();
+
+    let image = Image::from_channels(
+        (width as usize, height as usize),
+        AnyChannels::sort(SmallVec::from_vec(channels)),
+    );
+
+    image
+        .write()
+        .to_file(path)
+        .map_err(|source| ImageError::Exr {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+/// Encode a color as three mantissas sharing the exponent of the largest channel
+pub fn color_to_rgbe(color: Color) -> [u8; 4] {
+    // Fireflies can be infinite, they are stored as the largest value the format holds and NaN as black
+    let channel = |value: f32| match value.is_nan() {
+        true => 0.0,
+        false => value.clamp(0.0, f32::MAX),
+    };
+    let color = Color::new(channel(color.r), channel(color.g), channel(color.b));
+    let max = color.max_component();
+
+    if max < 1e-32 {
+        return [0; 4];
+    }
+
+    // max = mantissa * 2^exponent with the mantissa in [0.5, 1)
+    let exponent = (max.log2().floor() as i32 + 1).min(127);
+    let scale = 256.0 / 2f32.powi(exponent);
+
+    [
+        (color.r * scale).min(255.0) as u8,
+        (color.g * scale).min(255.0) as u8,
+        (color.b * scale).min(255.0) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Decode a color stored as shared exponent RGBE
+pub fn rgbe_to_color(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::default();
+    }
+
+    let scale = 2f32.powi(rgbe[3] as i32 - 136);
+    Color::new(
+        (rgbe[0] as f32 + 0.5) * scale,
+        (rgbe[1] as f32 + 0.5) * scale,
+        (rgbe[2] as f32 + 0.5) * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use crate::{color::Color, test_util::temp_path};
+
+    use super::{
+        color_to_rgbe, decode_hdr, read_hdr, read_png, rgbe_to_color, write_exr, write_hdr,
+        write_png, ImageChannel, ImageFormat,
+    };
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("render.PNG")),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("out/render.hdr")),
+            Some(ImageFormat::Hdr)
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("render.jpg")), None);
+        assert_eq!(ImageFormat::from_path(Path::new("render")), None);
+    }
+
+    #[test]
+    fn rgbe_round_trip() {
+        for color in [
+            Color::new(1.0, 0.5, 0.25),
+            Color::new(100.0, 0.01, 3.0),
+            Color::new(0.001, 0.002, 0.003),
+        ] {
+            let decoded = rgbe_to_color(color_to_rgbe(color));
+            let tolerance = color.max_component() / 128.0;
+
+            assert!((decoded.r - color.r).abs() <= tolerance);
+            assert!((decoded.g - color.g).abs() <= tolerance);
+            assert!((decoded.b - color.b).abs() <= tolerance);
+        }
+
+        assert_eq!(color_to_rgbe(Color::default()), [0; 4]);
+        assert_eq!(color_to_rgbe(Color::new(f32::NAN, 0.0, 0.0)), [0; 4]);
+
+        let infinite = rgbe_to_color(color_to_rgbe(Color::new(f32::INFINITY, 1.0, 0.0)));
+        assert!(infinite.r > 1e38 && infinite.r.is_finite());
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let path = temp_path("image.png");
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [1, 2, 3, 255],
+        ];
+        write_png(&path, 2, 2, &pixels).unwrap();
+        let image = read_png(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, pixels);
+    }
+
+    #[test]
+    fn exr_stores_named_channels() {
+        use exr::prelude::{read_all_flat_layers_from_file, FlatSamples};
+
+        let path = temp_path("image.exr");
+        let channel = |name: &str, values: Vec<f32>| ImageChannel {
+            name: name.to_owned(),
+            values,
+        };
+        let channels = vec![
+            channel("R", vec![5.0, 0.0]),
+            channel("G", vec![0.5, 0.0]),
+            channel("B", vec![0.0, -1.0]),
+            channel("depth.Z", vec![100.0, 2.5]),
+        ];
+        write_exr(&path, 2, 1, channels).unwrap();
+
+        let image = read_all_flat_layers_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let channels = &image.layer_data[0].channel_data.list;
+        let depth = channels
+            .iter()
+            .find(|channel| channel.name.to_string() == "depth.Z")
+            .unwrap();
+        let red = channels
+            .iter()
+            .find(|channel| channel.name.to_string() == "R")
+            .unwrap();
+
+        assert_eq!(channels.len(), 4);
+        assert!(matches!(&depth.sample_data, FlatSamples::F32(values) if values == &[100.0, 2.5]));
+        assert!(matches!(&red.sample_data, FlatSamples::F32(values) if values == &[5.0, 0.0]));
+    }
+
+    #[test]
+    fn hdr_round_trip() {
+        let path = temp_path("image.hdr");
+        let colors = [
+            Color::new(4.0, 0.5, 0.25),
+            Color::new(0.0, 0.0, 0.0),
+            Color::new(0.125, 1.0, 16.0),
+        ];
+        write_hdr(&path, 3, 1, &colors).unwrap();
+        let image = read_hdr(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((image.width, image.height), (3, 1));
+        assert_eq!(image.colors[1], Color::default());
+        assert!((image.colors[0].r - 4.0).abs() < 4.0 / 128.0);
+        assert!((image.colors[2].b - 16.0).abs() < 16.0 / 128.0);
+    }
+
+    #[test]
+    fn hdr_run_length_decoding() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        bytes.extend([2, 2, 0, 8]);
+        // Red is a run of 8, green 8 literal values, blue and exponent runs of 4
+        bytes.extend([136, 128]);
+        bytes.extend([8, 0, 16, 32, 48, 64, 80, 96, 112]);
+        bytes.extend([132, 0, 132, 128]);
+        bytes.extend([136, 129]);
+
+        let image = decode_hdr(&bytes).unwrap();
+
+        assert_eq!(image.colors.len(), 8);
+        assert_eq!(image.colors[0], rgbe_to_color([128, 0, 0, 129]));
+        assert_eq!(image.colors[7], rgbe_to_color([128, 112, 128, 129]));
+        assert!(decode_hdr(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode_hdr(b"P6\n").is_err());
+        assert!(decode_hdr(b"#?RADIANCE\n\n-Y 0 +X 0\n").is_err());
+        assert!(decode_hdr(b"#?RADIANCE\n\n-Y 4000000000 +X 4000000000\n").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+pub mod gltf;
+pub mod obj;
+
+use std::{fmt, io, path::PathBuf};
+
+pub use self::{gltf::import_gltf, obj::import_obj};
+
+/// Error returned when importing a file into a scene fails
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file couldn't be read
+    Io { path: PathBuf, source: io::Error },
+    /// The glTF file is malformed or references data that couldn't be loaded
+    Gltf {
+        path: PathBuf,
+        source: ::gltf::Error,
+    },
+    /// A text file such as an OBJ or MTL file contains an invalid statement
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// The file uses a feature the renderer doesn't support
+    Unsupported { path: PathBuf, feature: String },
+    /// The file parsed but contains inconsistent data, such as an index past the end of the vertices
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io { path, source } => {
+                write!(f, "{}: reading failed: {source}", path.display())
+            }
+            ImportError::Gltf { path, source } => {
+                write!(f, "{}: invalid glTF: {source}", path.display())
+            }
+            ImportError::Parse {
+                path,
+                line,
+                message,
+            } => {
+                write!(f, "{}:{line}: {message}", path.display())
+            }
+            ImportError::Unsupported { path, feature } => {
+                write!(f, "{}: unsupported feature: {feature}", path.display())
+            }
+            ImportError::Invalid { path, message } => {
+                write!(f, "{}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io { source, .. } => Some(source),
+            ImportError::Gltf { source, .. } => Some(source),
+            ImportError::Parse { .. }
+            | ImportError::Unsupported { .. }
+            | ImportError::Invalid { .. } => None,
+        }
+    }
+}
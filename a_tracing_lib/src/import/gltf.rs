@@ -0,0 +1,562 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ::gltf::{
+    buffer, camera::Projection, image, khr_lights_punctual::Kind, mesh::Mode, Document, Gltf, Node,
+};
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::{
+    color::{self, srgb_to_linear, Color},
+    image::ImageFormat,
+    scene::{
+        camera::PerspectiveCamera,
+        light::{DirectionalLight, Light, PointLight, SpotLight},
+        material::{Material, PbrMaterial},
+        mesh::TriangleMesh,
+        object::Object,
+        texture::Texture,
+        Scene,
+    },
+};
+
+use super::ImportError;
+
+/// Extensions a file can list as required and still be imported
+const SUPPORTED_EXTENSIONS: [&str; 2] = ["KHR_lights_punctual", "KHR_materials_emissive_strength"];
+
+/// Import the default scene of a .gltf or .glb file into the given scene
+/// Every mesh primitive becomes an object with its node transform applied to the vertices,
+/// punctual lights become lights and the first camera found replaces the camera of the scene.
+/// The aspect ratio of the existing camera is kept since it has to match the viewport.
+/// Nothing is added to the scene if the import fails.
+pub fn import_gltf<P: AsRef<Path>>(scene: &mut Scene, path: P) -> Result<(), ImportError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|source| ImportError::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+
+    let gltf_error = |source| ImportError::Gltf {
+        path: path.to_owned(),
+        source,
+    };
+
+    // Check the required extensions before validating so the error names the extension
+    let unvalidated = Gltf::from_slice_without_validation(&bytes).map_err(gltf_error)?;
+    if let Some(extension) = unvalidated
+        .extensions_required()
+        .find(|extension| !SUPPORTED_EXTENSIONS.contains(extension))
+    {
+        return Err(ImportError::Unsupported {
+            path: path.to_owned(),
+            feature: format!("required extension {extension}"),
+        });
+    }
+
+    let Gltf { document, blob } = Gltf::from_slice(&bytes).map_err(gltf_error)?;
+    let base = path.parent();
+    let buffers = ::gltf::import_buffers(&document, base, blob).map_err(gltf_error)?;
+    let images = ::gltf::import_images(&document, base, &buffers).map_err(gltf_error)?;
+
+    // PNG files next to the glTF file are referenced by textures saved to a scene file
+    let image_paths = document
+        .images()
+        .map(|image| match image.source() {
+            image::Source::Uri { uri, .. } => Some(base.unwrap_or(Path::new("")).join(uri))
+                .filter(|path| ImageFormat::from_path(path) == Some(ImageFormat::Png))
+                .filter(|path| path.is_file()),
+            image::Source::View { .. } => None,
+        })
+        .collect();
+
+    let mut importer = GltfImporter {
+        path: path.to_owned(),
+        aspect_ratio: scene.camera.aspect_ratio(),
+        buffers,
+        images,
+        image_paths,
+        materials: vec![None; document.materials().len()],
+        textures: Vec::new(),
+        objects: Vec::new(),
+        lights: Vec::new(),
+        camera: None,
+    };
+    importer.import_document(&document)?;
+
+    for object in importer.objects {
+        scene.add_object(object);
+    }
+
+    for light in importer.lights {
+        scene.add_boxed_light(light);
+    }
+
+    if let Some(camera) = importer.camera {
+        scene.camera = camera;
+    }
+
+    Ok(())
+}
+
+/// Everything loaded from the file so far, only added to the scene once the whole file imported successfully
+struct GltfImporter {
+    path: PathBuf,
+    aspect_ratio: f32,
+    buffers: Vec<buffer::Data>,
+    images: Vec<image::Data>,
+    /// Files of the images that aren't embedded and can be read again, by glTF image index
+    image_paths: Vec<Option<PathBuf>>,
+    /// Converted materials by glTF material index
+    materials: Vec<Option<Arc<dyn Material>>>,
+    /// Converted textures by glTF image index and whether they were decoded as sRGB
+    textures: Vec<((usize, bool), Arc<Texture>)>,
+    objects: Vec<Object>,
+    lights: Vec<Box<dyn Light>>,
+    camera: Option<PerspectiveCamera>,
+}
+
+impl GltfImporter {
+    fn unsupported(&self, feature: impl Into<String>) -> ImportError {
+        ImportError::Unsupported {
+            path: self.path.clone(),
+            feature: feature.into(),
+        }
+    }
+
+    fn invalid(&self, message: impl Into<String>) -> ImportError {
+        ImportError::Invalid {
+            path: self.path.clone(),
+            message: message.into(),
+        }
+    }
+
+    fn import_document(&mut self, document: &Document) -> Result<(), ImportError> {
+        let Some(gltf_scene) = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        else {
+            return Ok(());
+        };
+
+        for node in gltf_scene.nodes() {
+            self.import_node(&node, Mat4::IDENTITY)?;
+        }
+
+        Ok(())
+    }
+
+    fn import_node(&mut self, node: &Node, parent_transform: Mat4) -> Result<(), ImportError> {
+        let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.import_primitive(&primitive, transform)?;
+            }
+        }
+
+        if let Some(light) = node.light() {
+            self.import_light(&light, transform)?;
+        }
+
+        if let Some(camera) = node.camera() {
+            if self.camera.is_none() {
+                self.import_camera(&camera, transform)?;
+            }
+        }
+
+        for child in node.children() {
+            self.import_node(&child, transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn import_primitive(
+        &mut self,
+        primitive: &::gltf::Primitive,
+        transform: Mat4,
+    ) -> Result<(), ImportError> {
+        if primitive.mode() != Mode::Triangles {
+            return Err(self.unsupported(format!("primitive mode {:?}", primitive.mode())));
+        }
+
+        let buffers = &self.buffers;
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+
+        let positions: Vec<Vec3> = reader
+            .read_positions()
+            .ok_or_else(|| self.unsupported("primitive without positions"))?
+            .map(|position| transform.transform_point3(Vec3::from(position)))
+            .collect();
+
+        let vertex_indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if let Some(index) = vertex_indices
+            .iter()
+            .find(|&&index| index as usize >= positions.len())
+        {
+            return Err(self.invalid(format!(
+                "vertex index {index} of a primitive with {} vertices",
+                positions.len()
+            )));
+        }
+        let triangles = vertex_indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let normal_transform = transform.inverse().transpose();
+        let normals = reader.read_normals().map(|normals| {
+            normals
+                .map(|normal| normal_transform.transform_vector3(Vec3::from(normal)))
+                .collect::<Vec<_>>()
+        });
+        let uvs = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(Vec2::from).collect::<Vec<_>>());
+
+        // Every attribute has one value per vertex
+        for (attribute, count) in [
+            ("NORMAL", normals.as_ref().map(Vec::len)),
+            ("TEXCOORD_0", uvs.as_ref().map(Vec::len)),
+        ] {
+            if let Some(count) = count.filter(|&count| count != positions.len()) {
+                return Err(self.invalid(format!(
+                    "{attribute} has {count} values for {} vertices",
+                    positions.len()
+                )));
+            }
+        }
+
+        let mut mesh = TriangleMesh::new(positions, triangles);
+        if let Some(normals) = normals {
+            mesh = mesh.with_normals(normals);
+        }
+        if let Some(uvs) = uvs {
+            mesh = mesh.with_uvs(uvs);
+        }
+
+        let material = self.material(&primitive.material())?;
+        self.objects
+            .push(Object::with_shared_material(mesh, material));
+
+        Ok(())
+    }
+
+    fn material(&mut self, material: &::gltf::Material) -> Result<Arc<dyn Material>, ImportError> {
+        let Some(index) = material.index() else {
+            // The glTF default material
+            return Ok(Arc::new(PbrMaterial::new(color::WHITE, 1.0, 1.0)));
+        };
+
+        if let Some(converted) = &self.materials[index] {
+            return Ok(Arc::clone(converted));
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _alpha] = pbr.base_color_factor();
+        let emission =
+            Color::from(material.emissive_factor()) * material.emissive_strength().unwrap_or(1.0);
+
+        let mut converted = PbrMaterial::new(
+            Color::new(r, g, b),
+            pbr.metallic_factor(),
+            pbr.roughness_factor(),
+        )
+        .with_emission(emission);
+
+        if let Some(info) = pbr.base_color_texture() {
+            let texture = self.texture(info.texture().source().index(), true)?;
+            converted = converted.with_base_color_texture(texture);
+        }
+
+        if let Some(info) = pbr.metallic_roughness_texture() {
+            let texture = self.texture(info.texture().source().index(), false)?;
+            converted = converted.with_metallic_roughness_texture(texture);
+        }
+
+        let converted: Arc<dyn Material> = Arc::new(converted);
+        self.materials[index] = Some(Arc::clone(&converted));
+
+        Ok(converted)
+    }
+
+    /// Convert an image to a texture, color textures are stored in sRGB and decoded to linear
+    fn texture(&mut self, image_index: usize, srgb: bool) -> Result<Arc<Texture>, ImportError> {
+        let key = (image_index, srgb);
+        if let Some((_, texture)) = self.textures.iter().find(|(k, _)| *k == key) {
+            return Ok(Arc::clone(texture));
+        }
+
+        let image = &self.images[image_index];
+        let channels = match image.format {
+            image::Format::R8 => 1,
+            image::Format::R8G8 => 2,
+            image::Format::R8G8B8 => 3,
+            image::Format::R8G8B8A8 => 4,
+            format => return Err(self.unsupported(format!("texture format {format:?}"))),
+        };
+
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            if srgb {
+                srgb_to_linear(value)
+            } else {
+                value
+            }
+        };
+
+        let texels = image
+            .pixels
+            .chunks_exact(channels)
+            .map(|texel| match texel {
+                [value] | [value, _] => {
+                    let value = decode(*value);
+                    Color::new(value, value, value)
+                }
+                [r, g, b, ..] => Color::new(decode(*r), decode(*g), decode(*b)),
+                _ => unreachable!("Texels have between 1 and 4 channels."),
+            })
+            .collect();
+
+        let mut texture = Texture::new(image.width, image.height, texels);
+        if let Some(path) = &self.image_paths[image_index] {
+            texture = texture.with_source(path, srgb);
+        }
+
+        let texture = Arc::new(texture);
+        self.textures.push((key, Arc::clone(&texture)));
+
+        Ok(texture)
+    }
+
+    fn import_light(
+        &mut self,
+        light: &::gltf::khr_lights_punctual::Light,
+        transform: Mat4,
+    ) -> Result<(), ImportError> {
+        let color = Color::from(light.color()) * light.intensity();
+
+        match light.kind() {
+            Kind::Directional => {
+                // Lights shine along their local negative z axis
+                let direction = transform.transform_vector3(Vec3::NEG_Z);
+                self.lights
+                    .push(Box::new(DirectionalLight::new(color, direction)));
+                Ok(())
+            }
+            Kind::Point => {
+                let position = transform.transform_point3(Vec3::ZERO);
+                self.lights.push(Box::new(PointLight::new(color, position)));
+                Ok(())
+            }
+            Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                let position = transform.transform_point3(Vec3::ZERO);
+                let direction = transform.transform_vector3(Vec3::NEG_Z);
+                self.lights.push(Box::new(SpotLight::new(
+                    color,
+                    position,
+                    direction,
+                    inner_cone_angle.min(outer_cone_angle),
+                    outer_cone_angle,
+                )));
+                Ok(())
+            }
+        }
+    }
+
+    fn import_camera(
+        &mut self,
+        camera: &::gltf::Camera,
+        transform: Mat4,
+    ) -> Result<(), ImportError> {
+        let Projection::Perspective(perspective) = camera.projection() else {
+            return Err(self.unsupported("orthographic cameras"));
+        };
+
+        // Cameras look along their local negative z axis with y up
+        self.camera = Some(PerspectiveCamera::new(
+            transform.transform_point3(Vec3::ZERO),
+            transform.transform_vector3(Vec3::NEG_Z).normalize(),
+            transform.transform_vector3(Vec3::Y).normalize(),
+            perspective.yfov(),
+            self.aspect_ratio,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use glam::Vec3;
+
+    use crate::{
+        ray::Ray,
+        scene::{description::LightDescription, Scene},
+        test_util::temp_path,
+    };
+
+    use super::{import_gltf, ImportError};
+
+    /// A single triangle in the xy plane with a red material, a camera and a directional light
+    const TRIANGLE_GLTF: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [{ "type": "directional", "color": [1.0, 1.0, 1.0], "intensity": 2.0 }]
+            }
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [0, 1, 2] }],
+        "nodes": [
+            { "mesh": 0, "translation": [0.0, 0.0, -1.0] },
+            { "camera": 0, "translation": [0.0, 0.0, 3.0] },
+            { "extensions": { "KHR_lights_punctual": { "light": 0 } } }
+        ],
+        "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.8, "znear": 0.1 } }],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1.0, 0.0, 0.0, 1.0] } }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+        "accessors": [{
+            "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+            "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+        }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "buffers": [{
+            "byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+        }]
+    }"#;
+
+    #[test]
+    fn import_triangle() {
+        let path = temp_path("triangle.gltf");
+        fs::write(&path, TRIANGLE_GLTF).unwrap();
+        let mut scene = Scene::new(Default::default());
+        let result = import_gltf(&mut scene, &path);
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        assert_eq!(scene.objects().len(), 1);
+        assert_eq!(scene.lights().count(), 1);
+
+        // The node translation moves the triangle to z = -1
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, 1.0),
+            direction: Vec3::new(0.0, 0.0, -1.0),
+        };
+        let (_, record) = scene.first_hit(&ray).unwrap();
+        assert!((record.point.z + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn import_point_and_spot_lights() {
+        let contents = TRIANGLE_GLTF
+            .replacen(
+                r#"[{ "type": "directional", "color": [1.0, 1.0, 1.0], "intensity": 2.0 }]"#,
+                r#"[
+                    { "type": "point", "intensity": 5.0 },
+                    { "type": "spot", "spot": { "innerConeAngle": 0.2, "outerConeAngle": 0.4 } }
+                ]"#,
+                1,
+            )
+            .replacen(
+                r#"{ "extensions": { "KHR_lights_punctual": { "light": 0 } } }"#,
+                r#"{ "extensions": { "KHR_lights_punctual": { "light": 0 } }, "translation": [1.0, 2.0, 3.0] },
+                { "extensions": { "KHR_lights_punctual": { "light": 1 } } }"#,
+                1,
+            )
+            .replacen(r#""nodes": [0, 1, 2]"#, r#""nodes": [0, 1, 2, 3]"#, 1);
+        let path = temp_path("lights.gltf");
+        fs::write(&path, &contents).unwrap();
+        let mut scene = Scene::new(Default::default());
+        let result = import_gltf(&mut scene, &path);
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let lights: Vec<_> = scene
+            .lights()
+            .filter_map(|light| light.describe())
+            .collect();
+        assert!(matches!(
+            lights[0],
+            LightDescription::Point { intensity, position }
+                if intensity.r == 5.0 && position == Vec3::new(1.0, 2.0, 3.0)
+        ));
+        assert!(matches!(
+            lights[1],
+            LightDescription::Spot { direction, outer_angle_degrees, .. }
+                if direction == Vec3::NEG_Z && (outer_angle_degrees - 0.4f32.to_degrees()).abs() < 1e-4
+        ));
+    }
+
+    #[test]
+    fn index_out_of_range_is_rejected() {
+        let contents = TRIANGLE_GLTF
+            .replacen(
+                r#""attributes": { "POSITION": 0 },"#,
+                r#""attributes": { "POSITION": 0 }, "indices": 1,"#,
+                1,
+            )
+            .replacen(
+                r#""min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+        }],"#,
+                r#""min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+        }, { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }],"#,
+                1,
+            )
+            .replacen(
+                r#""bufferViews": [{ "buffer": 0, "byteLength": 36 }],"#,
+                r#""bufferViews": [
+            { "buffer": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+        ],"#,
+                1,
+            )
+            .replacen(
+                r#""byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA""#,
+                r#""byteLength": 44,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAcAAAA=""#,
+                1,
+            );
+        let path = temp_path("out_of_range.gltf");
+        fs::write(&path, &contents).unwrap();
+        let mut scene = Scene::new(Default::default());
+        let result = import_gltf(&mut scene, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ImportError::Invalid { .. })));
+        assert!(scene.objects().is_empty());
+    }
+
+    #[test]
+    fn unsupported_extension_is_rejected() {
+        let contents = TRIANGLE_GLTF.replacen(
+            r#""extensionsUsed": ["KHR_lights_punctual"],"#,
+            r#""extensionsUsed": ["KHR_draco_mesh_compression"],
+            "extensionsRequired": ["KHR_draco_mesh_compression"],"#,
+            1,
+        );
+        let path = temp_path("draco.gltf");
+        fs::write(&path, &contents).unwrap();
+        let mut scene = Scene::new(Default::default());
+        let result = import_gltf(&mut scene, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ImportError::Unsupported { .. })));
+        assert!(scene.objects().is_empty());
+    }
+}
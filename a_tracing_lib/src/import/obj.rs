@@ -0,0 +1,578 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    color::{self, Color},
+    scene::{
+        material::{Dielectric, Emissive, Lambertian, Material, PbrMaterial, Phong},
+        mesh::TriangleMesh,
+        object::Object,
+        Scene,
+    },
+};
+
+use super::ImportError;
+
+/// Import all faces of a Wavefront .obj file and the materials of its .mtl libraries into the given scene
+/// Every object, group and material change starts a new triangle mesh, polygons are split into triangle fans.
+/// Statements that don't describe geometry or materials, such as smoothing groups and texture maps, are skipped.
+/// Nothing is added to the scene if the import fails.
+pub fn import_obj<P: AsRef<Path>>(scene: &mut Scene, path: P) -> Result<(), ImportError> {
+    let path = path.as_ref();
+    let source = read_to_string(path)?;
+
+    let mut importer = ObjImporter::new(path);
+    for (index, line) in source.lines().enumerate() {
+        importer.line = index + 1;
+        importer.parse_line(line)?;
+    }
+    importer.finish_mesh();
+
+    for object in importer.objects {
+        scene.add_object(object);
+    }
+
+    Ok(())
+}
+
+fn read_to_string(path: &Path) -> Result<String, ImportError> {
+    fs::read_to_string(path).map_err(|source| ImportError::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Split a line into its keyword and arguments, ignoring comments
+fn split_statement(line: &str) -> Option<(&str, &str)> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(
+        line.split_once(char::is_whitespace)
+            .map_or((line, ""), |(keyword, arguments)| {
+                (keyword, arguments.trim())
+            }),
+    )
+}
+
+/// Position in a file being parsed, used to point errors at the offending line
+struct Location<'a> {
+    path: &'a Path,
+    line: usize,
+}
+
+impl Location<'_> {
+    fn error(&self, message: impl Into<String>) -> ImportError {
+        ImportError::Parse {
+            path: self.path.to_owned(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    /// Parse the first N numbers of the arguments, further numbers such as the optional w coordinate are ignored
+    fn parse_floats<const N: usize>(&self, arguments: &str) -> Result<[f32; N], ImportError> {
+        let mut values = [0.0; N];
+        let mut numbers = arguments.split_whitespace();
+
+        for value in &mut values {
+            let number = numbers
+                .next()
+                .ok_or_else(|| self.error(format!("expected {N} numbers")))?;
+            *value = number
+                .parse()
+                .ok()
+                .filter(|value: &f32| value.is_finite())
+                .ok_or_else(|| self.error(format!("invalid number '{number}'")))?;
+        }
+
+        Ok(values)
+    }
+
+    fn parse_float(&self, arguments: &str) -> Result<f32, ImportError> {
+        self.parse_floats::<1>(arguments).map(|[value]| value)
+    }
+
+    fn parse_color(&self, arguments: &str) -> Result<Color, ImportError> {
+        self.parse_floats(arguments).map(Color::from)
+    }
+}
+
+/// Indices into the position, texture coordinate and normal lists of a single face corner
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// Triangles that share an object, group and material, converted to a triangle mesh once complete
+struct MeshBuilder {
+    material: Arc<dyn Material>,
+    vertices: HashMap<VertexKey, u32>,
+    positions: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+    /// Texture coordinates and normals are only used if every face corner has them
+    complete_uvs: bool,
+    complete_normals: bool,
+}
+
+impl MeshBuilder {
+    fn new(material: Arc<dyn Material>) -> Self {
+        Self {
+            material,
+            vertices: HashMap::new(),
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            complete_uvs: true,
+            complete_normals: true,
+        }
+    }
+
+    fn build(self) -> Option<Object> {
+        if self.indices.is_empty() {
+            return None;
+        }
+
+        let mut mesh = TriangleMesh::new(self.positions, self.indices);
+        if self.complete_normals {
+            mesh = mesh.with_normals(self.normals);
+        }
+        if self.complete_uvs {
+            mesh = mesh.with_uvs(self.uvs);
+        }
+
+        Some(Object::with_shared_material(mesh, self.material))
+    }
+}
+
+struct ObjImporter<'a> {
+    path: &'a Path,
+    line: usize,
+    positions: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    default_material: Arc<dyn Material>,
+    mesh: MeshBuilder,
+    objects: Vec<Object>,
+}
+
+impl<'a> ObjImporter<'a> {
+    fn new(path: &'a Path) -> Self {
+        let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(color::WHITE * 0.8));
+
+        Self {
+            path,
+            line: 0,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            materials: HashMap::new(),
+            mesh: MeshBuilder::new(Arc::clone(&default_material)),
+            default_material,
+            objects: Vec::new(),
+        }
+    }
+
+    fn location(&self) -> Location<'a> {
+        Location {
+            path: self.path,
+            line: self.line,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ImportError> {
+        let Some((keyword, arguments)) = split_statement(line) else {
+            return Ok(());
+        };
+        let location = self.location();
+
+        match keyword {
+            "v" => {
+                let position = location.parse_floats(arguments)?;
+                self.positions.push(Vec3::from(position));
+            }
+            "vt" => {
+                // OBJ puts v = 0 at the bottom of the image, textures here have their first row at v = 0
+                let [u, v] = location.parse_floats(arguments)?;
+                self.uvs.push(Vec2::new(u, 1.0 - v));
+            }
+            "vn" => {
+                let normal = location.parse_floats(arguments)?;
+                self.normals.push(Vec3::from(normal));
+            }
+            "f" => self.parse_face(arguments)?,
+            "o" | "g" => self.start_mesh(Arc::clone(&self.mesh.material)),
+            "usemtl" => {
+                let material = self
+                    .materials
+                    .get(arguments)
+                    .ok_or_else(|| location.error(format!("unknown material '{arguments}'")))?;
+                self.start_mesh(Arc::clone(material));
+            }
+            "mtllib" => {
+                if arguments.is_empty() {
+                    return Err(location.error("missing material library name"));
+                }
+
+                // A statement can name several libraries, a library that can't be read is reported at the statement
+                for name in arguments.split_whitespace() {
+                    let library = self.path.with_file_name(name);
+                    let materials = parse_mtl(&library).map_err(|err| match err {
+                        ImportError::Io { source, .. } => location.error(format!(
+                            "reading material library '{name}' failed: {source}"
+                        )),
+                        err => err,
+                    })?;
+                    self.materials.extend(materials);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_face(&mut self, arguments: &str) -> Result<(), ImportError> {
+        let location = self.location();
+        let corners = arguments
+            .split_whitespace()
+            .map(|corner| self.parse_corner(corner))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if corners.len() < 3 {
+            return Err(location.error("faces need at least 3 vertices"));
+        }
+
+        let indices: Vec<u32> = corners
+            .into_iter()
+            .map(|corner| self.vertex(corner))
+            .collect();
+
+        for i in 1..indices.len() - 1 {
+            self.mesh
+                .indices
+                .push([indices[0], indices[i], indices[i + 1]]);
+        }
+
+        Ok(())
+    }
+
+    /// Parse a face corner in the v, v/vt, v//vn or v/vt/vn form
+    fn parse_corner(&self, corner: &str) -> Result<VertexKey, ImportError> {
+        let mut parts = corner.split('/');
+        let position = parts.next().unwrap_or_default();
+        let uv = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+
+        if parts.next().is_some() {
+            return Err(self
+                .location()
+                .error(format!("invalid face vertex '{corner}'")));
+        }
+
+        Ok((
+            self.resolve_index(position, self.positions.len())?,
+            uv.map(|uv| self.resolve_index(uv, self.uvs.len()))
+                .transpose()?,
+            normal
+                .map(|normal| self.resolve_index(normal, self.normals.len()))
+                .transpose()?,
+        ))
+    }
+
+    /// Convert a one-based index, or a negative index counting back from the last element, to a zero-based one
+    fn resolve_index(&self, index: &str, count: usize) -> Result<usize, ImportError> {
+        let location = self.location();
+        let value: i64 = index
+            .parse()
+            .map_err(|_| location.error(format!("invalid index '{index}'")))?;
+
+        let resolved = if value < 0 {
+            count as i64 + value
+        } else {
+            value - 1
+        };
+
+        if resolved < 0 || resolved >= count as i64 {
+            return Err(location.error(format!("index {value} out of range")));
+        }
+
+        Ok(resolved as usize)
+    }
+
+    /// Index of the face corner in the current mesh, adding it if it wasn't used before
+    fn vertex(&mut self, key: VertexKey) -> u32 {
+        let mesh = &mut self.mesh;
+
+        if let Some(&index) = mesh.vertices.get(&key) {
+            return index;
+        }
+
+        let (position, uv, normal) = key;
+        let index = mesh.positions.len() as u32;
+        mesh.positions.push(self.positions[position]);
+
+        match uv {
+            Some(uv) => mesh.uvs.push(self.uvs[uv]),
+            None => mesh.complete_uvs = false,
+        }
+
+        match normal {
+            Some(normal) => mesh.normals.push(self.normals[normal]),
+            None => mesh.complete_normals = false,
+        }
+
+        mesh.vertices.insert(key, index);
+        index
+    }
+
+    fn start_mesh(&mut self, material: Arc<dyn Material>) {
+        let mesh = std::mem::replace(&mut self.mesh, MeshBuilder::new(material));
+        self.objects.extend(mesh.build());
+    }
+
+    fn finish_mesh(&mut self) {
+        self.start_mesh(Arc::clone(&self.default_material));
+    }
+}
+
+/// The statements of a single material in a .mtl file
+#[derive(Default)]
+struct MtlMaterial {
+    diffuse: Option<Color>,
+    specular: Option<Color>,
+    exponent: Option<f32>,
+    emission: Option<Color>,
+    transmission: Option<Color>,
+    ior: Option<f32>,
+    dissolve: Option<f32>,
+    illumination: Option<u32>,
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+}
+
+impl MtlMaterial {
+    /// Pick the material of the renderer that fits the parameters best
+    /// The PBR extension parameters take precedence, then emission, transparency and specular highlights
+    fn build(&self) -> Arc<dyn Material> {
+        let diffuse = self.diffuse.unwrap_or(color::WHITE * 0.8);
+        let emission = self.emission.unwrap_or(color::BLACK);
+
+        if self.roughness.is_some() || self.metallic.is_some() {
+            return Arc::new(
+                PbrMaterial::new(
+                    diffuse,
+                    self.metallic.unwrap_or(0.0),
+                    self.roughness.unwrap_or(1.0),
+                )
+                .with_emission(emission),
+            );
+        }
+
+        if emission.max_component() > 0.0 {
+            return Arc::new(Emissive::new(emission, 1.0));
+        }
+
+        let refracts = matches!(self.illumination, Some(4 | 6 | 7));
+        if refracts || self.dissolve.is_some_and(|dissolve| dissolve < 1.0) {
+            // Exporters often write an index of refraction of 0 when they don't know it
+            return Arc::new(Dielectric::with_tint(
+                self.ior.filter(|&ior| ior > 0.0).unwrap_or(1.5),
+                self.transmission.unwrap_or(color::WHITE),
+            ));
+        }
+
+        let specular = self.specular.unwrap_or(color::BLACK);
+        if self.illumination != Some(1) && specular.max_component() > 0.0 {
+            return Arc::new(Phong::new(
+                diffuse,
+                specular,
+                self.exponent.unwrap_or(1.0).max(0.0),
+            ));
+        }
+
+        Arc::new(Lambertian::new(diffuse))
+    }
+}
+
+/// Parse all materials of a .mtl material library
+fn parse_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ImportError> {
+    let source = read_to_string(path)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let Some((keyword, arguments)) = split_statement(line) else {
+            continue;
+        };
+        let location = Location {
+            path,
+            line: index + 1,
+        };
+
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(location.error("missing material name"));
+            }
+
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material.build());
+            }
+            current = Some((arguments.to_owned(), MtlMaterial::default()));
+            continue;
+        }
+
+        let Some((_, material)) = &mut current else {
+            return Err(location.error(format!("'{keyword}' before the first newmtl")));
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = Some(location.parse_color(arguments)?),
+            "Ks" => material.specular = Some(location.parse_color(arguments)?),
+            "Ke" => material.emission = Some(location.parse_color(arguments)?),
+            "Tf" => material.transmission = Some(location.parse_color(arguments)?),
+            "Ns" => material.exponent = Some(location.parse_float(arguments)?),
+            "Ni" => material.ior = Some(location.parse_float(arguments)?),
+            "d" => material.dissolve = Some(location.parse_float(arguments)?),
+            "Tr" => material.dissolve = Some(1.0 - location.parse_float(arguments)?),
+            "Pr" => material.roughness = Some(location.parse_float(arguments)?),
+            "Pm" => material.metallic = Some(location.parse_float(arguments)?),
+            "illum" => {
+                let illumination = arguments.parse().map_err(|_| {
+                    location.error(format!("invalid illumination model '{arguments}'"))
+                })?;
+                material.illumination = Some(illumination);
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material.build());
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{scene::Scene, test_util::temp_path};
+
+    use super::{import_obj, ImportError};
+
+    #[test]
+    fn import_quad() {
+        let dir = temp_path("obj_quad");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("quad.mtl"),
+            "newmtl shiny\nKd 0.8 0.1 0.1\nKs 0.2 0.2 0.2\nNs 32\nillum 2\n",
+        )
+        .unwrap();
+        fs::write(dir.join("emissive.mtl"), "newmtl glow\nKe 4 4 4\n").unwrap();
+        fs::write(
+            dir.join("quad.obj"),
+            "mtllib quad.mtl emissive.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             vn 0 0 1\n\
+             o quad\nusemtl shiny\n\
+             f -4/1/1 -3/2/1 -2/3/1 -1/4/1\n\
+             o triangle\nusemtl glow\n\
+             f 1 2 3\n",
+        )
+        .unwrap();
+
+        let mut scene = Scene::new(Default::default());
+        let result = import_obj(&mut scene, dir.join("quad.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+
+        assert_eq!(scene.objects().len(), 2);
+    }
+
+    #[test]
+    fn parse_error_names_line() {
+        let dir = temp_path("obj_error");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("broken.obj"),
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 4\n",
+        )
+        .unwrap();
+
+        let mut scene = Scene::new(Default::default());
+        let result = import_obj(&mut scene, dir.join("broken.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let Err(ImportError::Parse { path, line, .. }) = result else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(line, 4);
+        assert!(path.ends_with("broken.obj"));
+        assert!(scene.objects().is_empty());
+    }
+
+    #[test]
+    fn invalid_material_values() {
+        let dir = temp_path("obj_invalid_mtl");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("zero.mtl"), "newmtl glass\nNi 0.000000\nd 0.5\n").unwrap();
+        fs::write(dir.join("nan.mtl"), "newmtl shiny\nKs 1 1 1\nNs nan\n").unwrap();
+        let triangle = "v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\n";
+        fs::write(
+            dir.join("zero.obj"),
+            format!("mtllib zero.mtl\nusemtl glass\n{triangle}"),
+        )
+        .unwrap();
+        fs::write(dir.join("nan.obj"), format!("mtllib nan.mtl\n{triangle}")).unwrap();
+
+        let mut scene = Scene::new(Default::default());
+        let zero = import_obj(&mut scene, dir.join("zero.obj"));
+        let nan = import_obj(&mut scene, dir.join("nan.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        zero.unwrap();
+        assert_eq!(scene.objects().len(), 1);
+        let Err(ImportError::Parse { path, line, .. }) = nan else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(line, 3);
+        assert!(path.ends_with("nan.mtl"));
+    }
+
+    #[test]
+    fn missing_material_library_names_line() {
+        let dir = temp_path("obj_missing_mtl");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("missing.obj"),
+            "v 0 0 0
+mtllib missing.mtl
+",
+        )
+        .unwrap();
+
+        let mut scene = Scene::new(Default::default());
+        let result = import_obj(&mut scene, dir.join("missing.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let Err(ImportError::Parse {
+            path,
+            line,
+            message,
+        }) = result
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(line, 2);
+        assert!(path.ends_with("missing.obj"));
+        assert!(message.contains("missing.mtl"));
+    }
+}
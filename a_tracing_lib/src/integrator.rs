@@ -0,0 +1,150 @@
+pub mod ambient_occlusion;
+pub mod debug;
+pub mod path_tracer;
+pub mod whitted;
+
+use crate::{
+    color::{self, Color},
+    ray::{HitRecord, Ray},
+    sampling::SampleStream,
+    scene::{
+        description::IntegratorDescription,
+        light::{is_occluded, Light},
+        material::facing_normal,
+        object::Object,
+        Scene,
+    },
+};
+use glam::Vec3;
+
+pub use ambient_occlusion::AmbientOcclusionIntegrator;
+pub use debug::{DebugIntegrator, DebugMode};
+pub use path_tracer::PathTracer;
+pub use whitted::WhittedIntegrator;
+
+/// Rendering algorithm that computes the light arriving at the camera along a ray
+/// The integrator used for full renders is selected through the ```RenderSettings```
+pub trait Integrator: Send + Sync {
+    /// max_depth is the maximum number of surfaces a path can hit, integrators that don't bounce can ignore it
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        max_depth: u32,
+        sampler: &mut SampleStream,
+    ) -> Color;
+
+    /// Description used to save the integrator to a scene file, integrators without one can't be saved
+    fn describe(&self) -> Option<IntegratorDescription> {
+        None
+    }
+}
+
+/// Light arriving directly from every light of the scene at the hit point and reflected towards wo
+/// Lights taking several samples, such as area lights, contribute the average of their samples
+pub fn direct_light(
+    scene: &Scene,
+    object: &Object,
+    record: &HitRecord,
+    wo: Vec3,
+    sampler: &mut SampleStream,
+) -> Color {
+    sample_lights(scene, object, record, wo, sampler, false)
+}
+
+/// Like ```direct_light``` but lights that material sampling can hit as well are weighted with the power heuristic
+/// Integrators using it add the light found by the sampled material direction weighted with ```material_sample_weight```
+pub fn direct_light_mis(
+    scene: &Scene,
+    object: &Object,
+    record: &HitRecord,
+    wo: Vec3,
+    sampler: &mut SampleStream,
+) -> Color {
+    sample_lights(scene, object, record, wo, sampler, true)
+}
+
+/// Multiple importance sampling weight of light reached by following a direction sampled from a material
+/// origin is the point the direction was sampled at and material_pdf its solid angle pdf
+pub fn material_sample_weight(
+    light: &dyn Light,
+    scene: &Scene,
+    origin: Vec3,
+    direction: Vec3,
+    material_pdf: f32,
+) -> f32 {
+    let light_pdf = light.pdf(scene, origin, direction) * light.sample_count() as f32;
+    power_heuristic(material_pdf, light_pdf)
+}
+
+/// Power heuristic weight with an exponent of 2 of a sample with the given pdf against another strategy
+/// Pdfs of strategies taking several samples are multiplied by their sample count
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let squared = pdf * pdf;
+    let total = squared + other_pdf * other_pdf;
+
+    if total > 0.0 && total.is_finite() {
+        squared / total
+    } else if pdf.is_infinite() {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn sample_lights(
+    scene: &Scene,
+    object: &Object,
+    record: &HitRecord,
+    wo: Vec3,
+    sampler: &mut SampleStream,
+    mis: bool,
+) -> Color {
+    let mut color_sum = color::BLACK;
+    let material = &object.material;
+    let normal = facing_normal(record.normal, wo);
+
+    for light in scene.sampled_lights() {
+        let sample_count = light.sample_count();
+        let mut light_sum = color::BLACK;
+
+        for _ in 0..sample_count {
+            let Some(sample) = light.sample(scene, record.point, sampler) else {
+                continue;
+            };
+            if sample.pdf <= 0.0 {
+                continue;
+            }
+
+            let Some(wi) = sample.direction else {
+                light_sum += material.albedo(record) * sample.radiance / sample.pdf;
+                continue;
+            };
+
+            // Skip the shadow ray when the material doesn't reflect the light anyway
+            let cos_theta = normal.dot(wi).max(0.0);
+            let brdf = material.evaluate(wo, wi, record);
+            if cos_theta == 0.0
+                || brdf.max_component() <= 0.0
+                || is_occluded(scene, record.point, &sample)
+            {
+                continue;
+            }
+
+            let weight = if mis && !sample.is_delta {
+                power_heuristic(
+                    sample_count as f32 * sample.pdf,
+                    material.pdf(wo, wi, record),
+                )
+            } else {
+                1.0
+            };
+
+            light_sum += brdf * sample.radiance * (cos_theta * weight / sample.pdf);
+        }
+
+        color_sum += light_sum / sample_count as f32;
+    }
+
+    color_sum
+}
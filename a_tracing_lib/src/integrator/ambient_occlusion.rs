@@ -0,0 +1,67 @@
+use crate::{
+    color::{self, Color},
+    ray::Ray,
+    sampling::{cosine_sample_hemisphere, SampleStream},
+    scene::{description::IntegratorDescription, Scene},
+};
+
+use super::Integrator;
+
+/// Shades every surface by the fraction of its hemisphere that is not blocked by nearby geometry
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusionIntegrator {
+    /// Number of occlusion rays per camera ray
+    pub samples: u32,
+    /// Geometry further away than this distance doesn't occlude
+    pub distance: f32,
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(samples: u32, distance: f32) -> Self {
+        assert!(samples > 0);
+
+        Self { samples, distance }
+    }
+}
+
+impl Default for AmbientOcclusionIntegrator {
+    fn default() -> Self {
+        Self::new(4, 1.0)
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        _max_depth: u32,
+        sampler: &mut SampleStream,
+    ) -> Color {
+        let Some((_object, record)) = scene.first_hit(ray) else {
+            return scene.background_color(&ray.direction);
+        };
+
+        let unoccluded = (0..self.samples)
+            .filter(|_| {
+                let occlusion_ray = Ray {
+                    origin: record.point,
+                    direction: cosine_sample_hemisphere(record.normal, sampler.next_vec2()),
+                };
+
+                scene
+                    .first_hit(&occlusion_ray)
+                    .is_none_or(|(_, hit)| hit.t > self.distance)
+            })
+            .count();
+
+        color::WHITE * (unoccluded as f32 / self.samples as f32)
+    }
+
+    fn describe(&self) -> Option<IntegratorDescription> {
+        Some(IntegratorDescription::AmbientOcclusion {
+            samples: self.samples,
+            distance: self.distance,
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::Color,
+    ray::Ray,
+    sampling::SampleStream,
+    scene::{description::IntegratorDescription, Scene},
+};
+
+use super::Integrator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugMode {
+    /// World space normal mapped from [-1, 1] to [0, 1]
+    Normals,
+    /// Unlit surface color
+    Albedo,
+}
+
+/// Visualises a property of the first hit surface without any lighting
+#[derive(Debug, Clone, Copy)]
+pub struct DebugIntegrator {
+    pub mode: DebugMode,
+}
+
+impl DebugIntegrator {
+    pub const fn new(mode: DebugMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        _max_depth: u32,
+        _sampler: &mut SampleStream,
+    ) -> Color {
+        let Some((object, record)) = scene.first_hit(ray) else {
+            return scene.background_color(&ray.direction);
+        };
+
+        match self.mode {
+            DebugMode::Normals => Color::from_normal(record.normal),
+            DebugMode::Albedo => object.material.albedo(&record),
+        }
+    }
+
+    fn describe(&self) -> Option<IntegratorDescription> {
+        Some(IntegratorDescription::Debug { mode: self.mode })
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    color::{self, Color},
+    ray::Ray,
+    sampling::SampleStream,
+    scene::{description::IntegratorDescription, light::EnvironmentLight, Scene},
+};
+use glam::Vec3;
+
+use super::{direct_light, direct_light_mis, material_sample_weight, Integrator};
+
+/// Bounce after which paths are randomly terminated with russian roulette
+const RUSSIAN_ROULETTE_DEPTH: u32 = 3;
+
+/// Monte Carlo path tracer giving indirect lighting
+/// At every hit the lights are sampled directly and the path continues in a direction sampled from the material
+/// Light reached by both strategies is combined with multiple importance sampling, which keeps small lights and glossy surfaces free of fireflies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        max_depth: u32,
+        sampler: &mut SampleStream,
+    ) -> Color {
+        let mut radiance = color::BLACK;
+        let mut throughput = color::WHITE;
+        let mut ray = *ray;
+        // Point the ray was sampled at and the pdf of its direction, None for camera rays and specular bounces
+        // Lights hit after a sampled bounce were sampled directly there as well, so both are weighted
+        let mut material_sample: Option<(Vec3, f32)> = None;
+
+        for depth in 0..max_depth {
+            let Some((index, record)) = scene.first_hit_index(&ray) else {
+                let weight = match material_sample {
+                    Some((origin, pdf)) if scene.background.is_sampled() => {
+                        material_sample_weight(&EnvironmentLight, scene, origin, ray.direction, pdf)
+                    }
+                    _ => 1.0,
+                };
+                radiance += throughput * scene.background_color(&ray.direction) * weight;
+                break;
+            };
+
+            let object = &scene.objects()[index];
+            let wo = -ray.direction.normalize();
+            let weight = match (material_sample, scene.area_light(index)) {
+                (Some((origin, pdf)), Some(light)) => {
+                    material_sample_weight(light, scene, origin, ray.direction, pdf)
+                }
+                _ => 1.0,
+            };
+            radiance += throughput * object.material.emitted(wo, &record) * weight;
+
+            // The material sampled direction isn't followed after the last vertex, so its light samples aren't weighted
+            if depth + 1 == max_depth {
+                radiance += throughput * direct_light(scene, object, &record, wo, sampler);
+                break;
+            }
+            radiance += throughput * direct_light_mis(scene, object, &record, wo, sampler);
+
+            let Some(scatter) = object.material.scatter(wo, &record, sampler) else {
+                break;
+            };
+            throughput = throughput * scatter.attenuation;
+            material_sample = (!scatter.is_specular).then_some((record.point, scatter.pdf));
+
+            if depth >= RUSSIAN_ROULETTE_DEPTH {
+                let survival = throughput.max_component().clamp(0.05, 0.95);
+                if sampler.next_f32() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+
+            ray = Ray {
+                origin: record.point,
+                direction: scatter.direction,
+            };
+        }
+
+        radiance
+    }
+
+    fn describe(&self) -> Option<IntegratorDescription> {
+        Some(IntegratorDescription::PathTracer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::{
+        color::{self, Color},
+        integrator::Integrator,
+        ray::Ray,
+        sampling::{RandomSampler, SampleStream},
+        scene::{
+            background::Background,
+            camera::PerspectiveCamera,
+            disk::Disk,
+            material::{Emissive, Lambertian},
+            object::Object,
+            rectangle::Rectangle,
+            Scene,
+        },
+    };
+
+    use super::PathTracer;
+
+    /// A diffuse floor under a downward facing emitter, optionally sampled as an area light
+    fn lit_floor(area_light: bool) -> Scene {
+        let mut scene = Scene::new(PerspectiveCamera::default());
+        scene.background = Background::Color(color::BLACK);
+        scene.add_object(Object::new(
+            Disk::new(Vec3::ZERO, Vec3::Y, 10.0),
+            Lambertian::new(color::WHITE * 0.5),
+        ));
+
+        let emitter = Object::new(
+            Rectangle::new(
+                Vec3::new(-0.5, 1.0, -0.5),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ),
+            Emissive::new(color::WHITE, 4.0),
+        );
+        if area_light {
+            scene.add_area_light(emitter, 1);
+        } else {
+            scene.add_object(emitter);
+        }
+
+        scene
+    }
+
+    fn average_radiance(scene: &Scene, max_depth: u32, samples: u32) -> Color {
+        let generator = RandomSampler::new(7);
+        let ray = Ray {
+            origin: Vec3::new(0.0, 0.5, -1.0),
+            direction: Vec3::new(0.0, -0.5, 1.0),
+        };
+
+        let mut sum = color::BLACK;
+        for sample_number in 0..samples {
+            let mut sampler = SampleStream::new(&generator, 0, sample_number);
+            sum += PathTracer.radiance(scene, &ray, max_depth, &mut sampler);
+        }
+
+        sum / samples as f32
+    }
+
+    #[test]
+    fn light_sampling_matches_material_sampling() {
+        let sampled = average_radiance(&lit_floor(true), 2, 4000).g;
+        let unsampled = average_radiance(&lit_floor(false), 2, 20000).g;
+
+        assert!(sampled > 0.1);
+        assert!((sampled - unsampled).abs() < 0.05 * unsampled);
+    }
+
+    #[test]
+    fn direct_lighting_only_keeps_the_full_light_samples() {
+        // Without light sampling the emitter is only found by the bounce a depth of 2 allows
+        let sampled = average_radiance(&lit_floor(true), 1, 4000).g;
+        let unsampled = average_radiance(&lit_floor(false), 2, 20000).g;
+
+        assert!(sampled > 0.1);
+        assert!((sampled - unsampled).abs() < 0.05 * unsampled);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    color::{self, Color},
+    ray::Ray,
+    sampling::SampleStream,
+    scene::{description::IntegratorDescription, Scene},
+};
+
+use super::{direct_light, Integrator};
+
+/// Gathers the light arriving directly from the lights and follows perfect mirrors and glass
+/// without any indirect diffuse lighting, the behaviour of the original renderer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhittedIntegrator;
+
+impl Integrator for WhittedIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        max_depth: u32,
+        sampler: &mut SampleStream,
+    ) -> Color {
+        let mut radiance = color::BLACK;
+        let mut throughput = color::WHITE;
+        let mut ray = *ray;
+
+        for _ in 0..max_depth {
+            let Some((object, record)) = scene.first_hit(&ray) else {
+                radiance += throughput * scene.background_color(&ray.direction);
+                break;
+            };
+
+            let wo = -ray.direction.normalize();
+            radiance += throughput * object.material.emitted(wo, &record);
+            radiance += throughput * direct_light(scene, object, &record, wo, sampler);
+
+            match object.material.scatter(wo, &record, sampler) {
+                Some(scatter) if scatter.is_specular => {
+                    throughput = throughput * scatter.attenuation;
+                    ray = Ray {
+                        origin: record.point,
+                        direction: scatter.direction,
+                    };
+                }
+                _ => break,
+            }
+        }
+
+        radiance
+    }
+
+    fn describe(&self) -> Option<IntegratorDescription> {
+        Some(IntegratorDescription::Whitted)
+    }
+}
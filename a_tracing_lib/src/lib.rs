@@ -1,7 +1,13 @@
 pub mod color;
+pub mod image;
+pub mod import;
+pub mod integrator;
 pub mod ray;
 pub mod sampling;
 pub mod scene;
 pub mod tracer;
 
+#[cfg(test)]
+mod test_util;
+
 pub use glam::Vec3;
@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
@@ -17,4 +17,9 @@ pub struct HitRecord {
     pub point: Vec3,
     pub normal: Vec3,
     pub t: f32,
+    /// Texture coordinates of the hit point
+    pub uv: Vec2,
+    /// Barycentric coordinates of the hit point for the second and third vertex of the hit triangle
+    /// The weight of the first vertex is 1 - x - y, shapes that aren't triangles leave this at zero
+    pub barycentric: Vec2,
 }
@@ -1,8 +1,36 @@
-use glam::Vec2;
+pub mod low_discrepancy;
+pub mod random;
+pub mod stratified;
 
-pub trait SampleGenerator {
-    /// generates the requested sample with x and y in range [-0.5, 0.5] if there are samples left
-    fn get_sample(&self, sample_number: u32) -> Vec2;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::description::SamplerDescription;
+
+pub use low_discrepancy::{HaltonSampler, SobolSampler};
+pub use random::RandomSampler;
+pub use stratified::{JitteredSampler, MultiJitteredSampler};
+
+/// Generates the values of every random decision made while rendering a sample of a pixel
+/// Every sample is a point with any number of dimensions, the first two place it in the pixel and the next two on the lens
+/// Samplers should decorrelate the patterns of different pixels, otherwise their errors form visible structures
+pub trait SampleGenerator: Send + Sync {
+    /// Value in range [0, 1) of the given dimension of the sample
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32;
+
+    /// Point in the unit square [0, 1) x [0, 1) made of the given dimension and the one after it
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2;
+
+    /// Offset of the sample from the center of the pixel with x and y in range [-0.5, 0.5]
+    fn get_sample(&self, pixel_index: u32, sample_number: u32) -> Vec2 {
+        self.get_2d(pixel_index, sample_number, 0) - 0.5
+    }
+
+    /// Description used to save the sampler to a scene file, samplers without one can't be saved
+    fn describe(&self) -> Option<SamplerDescription> {
+        None
+    }
 }
 
 pub struct RegularSampler {
@@ -17,8 +45,17 @@ impl RegularSampler {
     }
 }
 
+/// Only the position in the pixel lies on the grid, the other dimensions are random
 impl SampleGenerator for RegularSampler {
-    fn get_sample(&self, sample_number: u32) -> Vec2 {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        dimension_rng(0, pixel_index, sample_number, dimension).next_f32()
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        if dimension != 0 {
+            return dimension_rng(0, pixel_index, sample_number, dimension).next_vec2();
+        }
+
         let sample_number = sample_number % (self.samples_per_side * self.samples_per_side);
 
         let stride = 1.0 / (self.samples_per_side as f32 + 1.0);
@@ -26,10 +63,309 @@ impl SampleGenerator for RegularSampler {
         let row = sample_number / self.samples_per_side + 1;
         let column = sample_number % self.samples_per_side + 1;
 
-        let x = stride * column as f32 - 0.5;
-        let y = stride * row as f32 - 0.5;
+        Vec2::new(stride * column as f32, stride * row as f32)
+    }
 
-        Vec2::new(x, y)
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::Regular {
+            samples_per_side: self.samples_per_side,
+        })
+    }
+}
+
+/// The dimensions of a single sample of a pixel, handed out in order
+/// Every random decision made while rendering the sample takes its values from here,
+/// so the result only depends on the sampler and its seed
+pub struct SampleStream<'a> {
+    generator: &'a dyn SampleGenerator,
+    pixel_index: u32,
+    sample_number: u32,
+    dimension: u32,
+}
+
+impl<'a> SampleStream<'a> {
+    pub fn new(generator: &'a dyn SampleGenerator, pixel_index: u32, sample_number: u32) -> Self {
+        Self {
+            generator,
+            pixel_index,
+            sample_number,
+            dimension: 0,
+        }
+    }
+
+    /// Value in range [0, 1) of the next dimension
+    pub fn next_f32(&mut self) -> f32 {
+        let value = self
+            .generator
+            .get_1d(self.pixel_index, self.sample_number, self.dimension);
+        self.dimension += 1;
+        value
+    }
+
+    /// Point in the unit square [0, 1) x [0, 1) made of the next two dimensions
+    pub fn next_vec2(&mut self) -> Vec2 {
+        let value = self
+            .generator
+            .get_2d(self.pixel_index, self.sample_number, self.dimension);
+        self.dimension += 2;
+        value
+    }
+}
+
+/// Small and fast PCG32 random number generator
+/// Every pixel sample gets its own generator so renders are deterministic regardless of the thread scheduling
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (seed << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(self.increment);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    /// Uniform float in range [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Uniform point in the unit square [0, 1) x [0, 1)
+    pub fn next_vec2(&mut self) -> Vec2 {
+        Vec2::new(self.next_f32(), self.next_f32())
+    }
+}
+
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Seed of the sample pattern of a pixel
+fn pixel_hash(seed: u32, pixel_index: u32) -> u32 {
+    split_mix(((seed as u64) << 32) | pixel_index as u64) as u32
+}
+
+/// Seed of the sample pattern of a single dimension of a pixel
+fn dimension_hash(seed: u32, pixel_index: u32, dimension: u32) -> u32 {
+    pixel_hash(hash_combine(seed, dimension), pixel_index)
+}
+
+fn hash_combine(seed: u32, value: u32) -> u32 {
+    seed ^ (value
+        .wrapping_add(0x9e3779b9)
+        .wrapping_add(seed << 6)
+        .wrapping_add(seed >> 2))
+}
+
+/// Independent random values for the given dimension of the sample
+fn dimension_rng(seed: u32, pixel_index: u32, sample_number: u32, dimension: u32) -> Rng {
+    let dimension_seed = dimension_hash(seed, pixel_index, dimension);
+    Rng::new(split_mix(
+        ((dimension_seed as u64) << 32) | sample_number as u64,
+    ))
+}
+
+/// Element at the index of a random permutation of 0..length chosen by the pattern, by Kensler
+fn permute(mut index: u32, length: u32, pattern: u32) -> u32 {
+    let mut mask = length - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    // Permute within the next power of two and retry until the result lands inside the length
+    loop {
+        index ^= pattern;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= pattern >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= pattern >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= pattern >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | pattern >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= mask;
+        index ^= index >> 5;
+
+        if index < length {
+            return index.wrapping_add(pattern) % length;
+        }
+    }
+}
+
+/// Float in range [0, 1) hashed from the index and pattern, by Kensler
+fn hash_to_f32(mut index: u32, pattern: u32) -> f32 {
+    index ^= pattern;
+    index ^= index >> 17;
+    index ^= index >> 10;
+    index = index.wrapping_mul(0xb36534e5);
+    index ^= index >> 12;
+    index ^= index >> 21;
+    index = index.wrapping_mul(0x93fc4795);
+    index ^= 0xdf6e307f;
+    index ^= index >> 17;
+    index = index.wrapping_mul(1 | pattern >> 18);
+
+    (index >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Map a uniform sample in the unit square to a cosine weighted direction around the given normal
+/// The pdf of the returned direction is cos(theta) / pi
+pub fn cosine_sample_hemisphere(normal: Vec3, sample: Vec2) -> Vec3 {
+    let radius = sample.x.sqrt();
+    let phi = TAU * sample.y;
+
+    let x = radius * phi.cos();
+    let y = radius * phi.sin();
+    let z = (1.0 - sample.x).max(0.0).sqrt();
+
+    let (tangent, bitangent) = normal.any_orthonormal_pair();
+    (x * tangent + y * bitangent + z * normal).normalize()
+}
+
+/// Map a uniform sample in the unit square to a uniformly distributed point on the unit disk
+/// Shirley's concentric mapping keeps neighbouring samples close together
+pub fn concentric_sample_disk(sample: Vec2) -> Vec2 {
+    let offset = 2.0 * sample - 1.0;
+
+    if offset == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+
+    let (radius, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (offset.y, FRAC_PI_2 - FRAC_PI_4 * (offset.x / offset.y))
+    };
+
+    radius * Vec2::new(theta.cos(), theta.sin())
+}
+
+/// Map a uniform sample in the unit square to a uniformly distributed direction on the unit sphere
+pub fn uniform_sample_sphere(sample: Vec2) -> Vec3 {
+    let z = 1.0 - 2.0 * sample.x;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let phi = TAU * sample.y;
+
+    Vec3::new(radius * phi.cos(), radius * phi.sin(), z)
+}
+
+/// Map a uniform sample in the unit square to a uniformly distributed direction inside a cone around the axis
+/// one_minus_cos_max is 1 - cos of the half angle of the cone, which keeps its precision for very narrow cones
+/// The pdf of the returned direction is 1 / (2 pi one_minus_cos_max)
+pub fn uniform_sample_cone(axis: Vec3, one_minus_cos_max: f32, sample: Vec2) -> Vec3 {
+    let one_minus_cos = sample.x * one_minus_cos_max;
+    let cos_theta = 1.0 - one_minus_cos;
+    let sin_theta = (one_minus_cos * (2.0 - one_minus_cos)).max(0.0).sqrt();
+    let phi = TAU * sample.y;
+
+    let (tangent, bitangent) = axis.any_orthonormal_pair();
+    (sin_theta * phi.cos() * tangent + sin_theta * phi.sin() * bitangent + cos_theta * axis)
+        .normalize()
+}
+
+/// Piecewise constant distribution over [0, 1) with one interval per weight
+/// Used to pick pixels of an environment map proportional to their brightness
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    weights: Vec<f32>,
+    /// Running sum of the weights normalized to end at 1
+    cdf: Vec<f32>,
+    /// Average of the weights, used to normalize the pdf
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// Negative weights count as zero, all zero weights give a uniform distribution
+    pub fn new(weights: Vec<f32>) -> Self {
+        assert!(
+            !weights.is_empty(),
+            "A distribution needs at least one weight."
+        );
+
+        let mut weights: Vec<f32> = weights.into_iter().map(|weight| weight.max(0.0)).collect();
+        let mut sum: f32 = weights.iter().sum();
+        if sum <= 0.0 || !sum.is_finite() {
+            weights.fill(1.0);
+            sum = weights.len() as f32;
+        }
+
+        let cdf = weights
+            .iter()
+            .scan(0.0, |running, weight| {
+                *running += weight;
+                Some(*running / sum)
+            })
+            .collect();
+
+        Self {
+            integral: sum / weights.len() as f32,
+            weights,
+            cdf,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Map a uniform sample to a point in [0, 1) and the index of its interval
+    /// Returns the point, the index and the pdf of the point
+    pub fn sample(&self, sample: f32) -> (f32, usize, f32) {
+        let index = self
+            .cdf
+            .partition_point(|&cdf| cdf <= sample)
+            .min(self.len() - 1);
+        let start = if index == 0 { 0.0 } else { self.cdf[index - 1] };
+        let width = self.cdf[index] - start;
+
+        // Reuse the position of the sample inside the interval
+        let offset = if width > 0.0 {
+            ((sample - start) / width).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        let point = ((index as f32 + offset) / self.len() as f32).min(1.0 - f32::EPSILON);
+
+        (point, index, self.pdf(index))
+    }
+
+    /// Density of the points inside the interval at the index
+    pub fn pdf(&self, index: usize) -> f32 {
+        self.weights[index] / self.integral
     }
 }
 
@@ -47,30 +383,223 @@ mod tests {
     #[test]
     fn test_regular_sampler_1_zero() {
         let sampler = RegularSampler::new(1);
-        assert_eq!(sampler.get_sample(0), vec2(0.0, 0.0));
+        assert_eq!(sampler.get_sample(0, 0), vec2(0.0, 0.0));
     }
 
     #[test]
     fn test_regular_sampler_1_any() {
         let sampler = RegularSampler::new(1);
-        assert_eq!(sampler.get_sample(7), vec2(0.0, 0.0));
+        assert_eq!(sampler.get_sample(0, 7), vec2(0.0, 0.0));
     }
 
     #[test]
     fn test_regular_sampler_4_zero() {
         let sampler = RegularSampler::new(4);
-        assert_eq!(sampler.get_sample(0), vec2(-0.3, -0.3));
+        assert_eq!(sampler.get_sample(0, 0), vec2(-0.3, -0.3));
     }
 
     #[test]
     fn test_regular_sampler_4_three() {
         let sampler = RegularSampler::new(4);
-        assert_eq!(sampler.get_sample(3), vec2(0.3, -0.3));
+        assert_eq!(sampler.get_sample(0, 3), vec2(0.3, -0.3));
     }
 
     #[test]
     fn test_regular_sampler_4_fifteen() {
         let sampler = RegularSampler::new(4);
-        assert_eq!(sampler.get_sample(15), vec2(0.3, 0.3));
+        assert_eq!(sampler.get_sample(0, 15), vec2(0.3, 0.3));
+    }
+
+    /// Number of samples in every cell of a grid with the given number of columns and rows
+    fn cell_counts(
+        sampler: &dyn SampleGenerator,
+        pixel_index: u32,
+        samples: u32,
+        columns: u32,
+        rows: u32,
+    ) -> Vec<u32> {
+        let mut counts = vec![0; (columns * rows) as usize];
+
+        for s in 0..samples {
+            let sample = sampler.get_sample(pixel_index, s);
+            assert!((-0.5..0.5).contains(&sample.x) && (-0.5..0.5).contains(&sample.y));
+
+            let column = ((sample.x + 0.5) * columns as f32) as u32;
+            let row = ((sample.y + 0.5) * rows as f32) as u32;
+            counts[(row * columns + column) as usize] += 1;
+        }
+
+        counts
+    }
+
+    #[test]
+    fn test_permute_is_permutation() {
+        for length in [1, 5, 16, 100] {
+            let mut values: Vec<_> = (0..length).map(|i| permute(i, length, 1234)).collect();
+            values.sort();
+            assert_eq!(values, (0..length).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_jittered_sampler_stratified() {
+        let sampler = JitteredSampler::new(4, 0);
+
+        for pixel_index in [0, 17] {
+            assert!(cell_counts(&sampler, pixel_index, 16, 4, 4)
+                .iter()
+                .all(|&count| count == 1));
+        }
+    }
+
+    #[test]
+    fn test_multi_jittered_sampler_stratified() {
+        let sampler = MultiJitteredSampler::new(4, 3);
+
+        assert!(cell_counts(&sampler, 5, 16, 4, 4)
+            .iter()
+            .all(|&count| count == 1));
+        assert!(cell_counts(&sampler, 5, 16, 16, 1)
+            .iter()
+            .all(|&count| count == 1));
+        assert!(cell_counts(&sampler, 5, 16, 1, 16)
+            .iter()
+            .all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_sobol_sampler_stratified() {
+        let sampler = SobolSampler::new(0);
+
+        for pixel_index in [0, 9] {
+            assert!(cell_counts(&sampler, pixel_index, 16, 4, 4)
+                .iter()
+                .all(|&count| count == 1));
+            assert!(cell_counts(&sampler, pixel_index, 16, 16, 1)
+                .iter()
+                .all(|&count| count == 1));
+        }
+    }
+
+    #[test]
+    fn test_radical_inverse() {
+        let base_2: Vec<_> = (0..4)
+            .map(|i| low_discrepancy::radical_inverse(2, i))
+            .collect();
+        let base_3: Vec<_> = (0..4)
+            .map(|i| low_discrepancy::radical_inverse(3, i))
+            .collect();
+
+        assert_eq!(base_2, vec![0.0, 0.5, 0.25, 0.75]);
+        assert_eq!(base_3, vec![0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]);
+    }
+
+    #[test]
+    fn test_halton_sampler_range() {
+        let sampler = HaltonSampler::new(0);
+        assert_eq!(cell_counts(&sampler, 3, 100, 1, 1), vec![100]);
+    }
+
+    #[test]
+    fn test_samplers_decorrelate_pixels() {
+        let samplers: [Box<dyn SampleGenerator>; 5] = [
+            Box::new(RandomSampler::new(0)),
+            Box::new(JitteredSampler::new(2, 0)),
+            Box::new(MultiJitteredSampler::new(2, 0)),
+            Box::new(HaltonSampler::new(0)),
+            Box::new(SobolSampler::new(0)),
+        ];
+
+        for sampler in samplers {
+            assert_ne!(sampler.get_sample(0, 0), sampler.get_sample(1, 0));
+            assert_eq!(sampler.get_sample(4, 2), sampler.get_sample(4, 2));
+        }
+    }
+
+    #[test]
+    fn test_higher_dimensions_stratified() {
+        let samplers: [Box<dyn SampleGenerator>; 3] = [
+            Box::new(JitteredSampler::new(4, 0)),
+            Box::new(MultiJitteredSampler::new(4, 0)),
+            Box::new(SobolSampler::new(0)),
+        ];
+
+        for sampler in samplers {
+            let mut cells_2d = [0; 16];
+            let mut cells_1d = [0; 16];
+
+            for s in 0..16 {
+                let point = sampler.get_2d(7, s, 5);
+                cells_2d[(point.y * 4.0) as usize * 4 + (point.x * 4.0) as usize] += 1;
+                cells_1d[(sampler.get_1d(7, s, 9) * 16.0) as usize] += 1;
+            }
+
+            assert!(cells_2d.iter().all(|&count| count == 1));
+            assert!(cells_1d.iter().all(|&count| count == 1));
+        }
+    }
+
+    #[test]
+    fn test_sample_stream_dimensions() {
+        let generator = SobolSampler::new(4);
+        let mut sampler = SampleStream::new(&generator, 3, 2);
+
+        assert_eq!(sampler.next_vec2(), generator.get_2d(3, 2, 0));
+        assert_eq!(sampler.next_f32(), generator.get_1d(3, 2, 2));
+        assert_eq!(sampler.next_vec2(), generator.get_2d(3, 2, 3));
+
+        // Different dimensions are independent of each other
+        assert_ne!(generator.get_2d(3, 2, 0), generator.get_2d(3, 2, 2));
+    }
+
+    #[test]
+    fn test_concentric_sample_disk() {
+        let mut rng = Rng::new(5);
+
+        assert_eq!(concentric_sample_disk(vec2(0.5, 0.5)), Vec2::ZERO);
+        assert!((concentric_sample_disk(vec2(1.0, 0.5)) - vec2(1.0, 0.0)).length() < 1e-6);
+        for _ in 0..100 {
+            assert!(concentric_sample_disk(rng.next_vec2()).length() <= 1.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_rng_range() {
+        let mut rng = Rng::new(12);
+        assert!((0..1000)
+            .map(|_| rng.next_f32())
+            .all(|x| (0.0..1.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_cosine_sample_in_hemisphere() {
+        let normal = Vec3::new(0.3, -0.8, 0.1).normalize();
+        let mut rng = Rng::new(7);
+
+        for _ in 0..100 {
+            let direction = cosine_sample_hemisphere(normal, rng.next_vec2());
+            assert!(direction.dot(normal) >= 0.0);
+            assert!((direction.length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_distribution_1d() {
+        let distribution = Distribution1D::new(vec![1.0, 0.0, 3.0]);
+        assert_eq!(distribution.pdf(1), 0.0);
+
+        let (point, index, pdf) = distribution.sample(0.1);
+        assert_eq!(index, 0);
+        assert!((point - 0.4 / 3.0).abs() < 1e-6);
+        assert_eq!(pdf, 0.75);
+
+        let (point, index, pdf) = distribution.sample(0.625);
+        assert_eq!(index, 2);
+        assert!((point - 2.5 / 3.0).abs() < 1e-6);
+        assert_eq!(pdf, 2.25);
+
+        let uniform = Distribution1D::new(vec![0.0, 0.0]);
+        assert_eq!(uniform.sample(0.75).1, 1);
+        assert_eq!(uniform.pdf(0), 1.0);
     }
 }
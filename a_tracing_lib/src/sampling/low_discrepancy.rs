@@ -0,0 +1,143 @@
+use glam::Vec2;
+
+use crate::scene::description::SamplerDescription;
+
+use super::{dimension_hash, dimension_rng, hash_combine, SampleGenerator};
+
+/// Radical inverse of the index in the given base, mirroring its digits around the decimal point
+pub fn radical_inverse(base: u32, mut index: u32) -> f32 {
+    let inverse_base = 1.0 / base as f64;
+    let mut scale = inverse_base;
+    let mut result = 0.0;
+
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse_base;
+    }
+
+    (result as f32).min(ONE_MINUS_EPSILON)
+}
+
+/// Largest float below 1
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Bases of the Halton dimensions, higher dimensions fall back to random values
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sequence using the n-th prime as the base of the n-th dimension
+/// Every pixel shifts every dimension by its own random offset, wrapping around [0, 1)
+pub struct HaltonSampler {
+    seed: u32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl SampleGenerator for HaltonSampler {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        let Some(&base) = PRIMES.get(dimension as usize) else {
+            return dimension_rng(self.seed, pixel_index, sample_number, dimension).next_f32();
+        };
+
+        let offset = to_unit_f32(dimension_hash(self.seed, pixel_index, dimension));
+        let value = radical_inverse(base, sample_number) + offset;
+        value.fract().min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        Vec2::new(
+            self.get_1d(pixel_index, sample_number, dimension),
+            self.get_1d(pixel_index, sample_number, dimension + 1),
+        )
+    }
+
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::Halton { seed: self.seed })
+    }
+}
+
+/// Owen scrambled Sobol sequence, padded by giving every pair of dimensions the first two Sobol dimensions
+/// with its own scramble and shuffled order, as described by Burley
+/// Every pixel uses its own scrambles, which keeps the stratification of the sequence at every power of two
+pub struct SobolSampler {
+    seed: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl SampleGenerator for SobolSampler {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        let dimension_seed = dimension_hash(self.seed, pixel_index, dimension);
+        let index = nested_uniform_scramble(sample_number, dimension_seed);
+
+        to_unit_f32(nested_uniform_scramble(
+            sobol_0(index),
+            hash_combine(dimension_seed, 0),
+        ))
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        let dimension_seed = dimension_hash(self.seed, pixel_index, dimension);
+        let index = nested_uniform_scramble(sample_number, dimension_seed);
+
+        let x = nested_uniform_scramble(sobol_0(index), hash_combine(dimension_seed, 0));
+        let y = nested_uniform_scramble(sobol_1(index), hash_combine(dimension_seed, 1));
+
+        Vec2::new(to_unit_f32(x), to_unit_f32(y))
+    }
+
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::Sobol { seed: self.seed })
+    }
+}
+
+/// First dimension of the Sobol sequence as a 32-bit fixed point fraction, the van der Corput sequence
+fn sobol_0(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+/// Second dimension of the Sobol sequence as a 32-bit fixed point fraction
+fn sobol_1(mut index: u32) -> u32 {
+    // The direction numbers of the second dimension are the rows of Pascal's triangle mod 2
+    let mut direction = 1 << 31;
+    let mut result = 0;
+
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+
+    result
+}
+
+/// Owen scramble of a fixed point fraction using the hash by Laine and Karras
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    let mut x = value.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
+
+/// Map a 32-bit fixed point fraction to a float in range [0, 1)
+fn to_unit_f32(value: u32) -> f32 {
+    (value >> 8) as f32 / (1 << 24) as f32
+}
@@ -0,0 +1,30 @@
+use glam::Vec2;
+
+use crate::scene::description::SamplerDescription;
+
+use super::{dimension_rng, SampleGenerator};
+
+/// Independent uniformly distributed samples, free of aliasing but noisier than the stratified samplers
+pub struct RandomSampler {
+    seed: u32,
+}
+
+impl RandomSampler {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl SampleGenerator for RandomSampler {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        dimension_rng(self.seed, pixel_index, sample_number, dimension).next_f32()
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        dimension_rng(self.seed, pixel_index, sample_number, dimension).next_vec2()
+    }
+
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::Random { seed: self.seed })
+    }
+}
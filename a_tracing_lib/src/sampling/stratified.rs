@@ -0,0 +1,127 @@
+use glam::Vec2;
+
+use crate::scene::description::SamplerDescription;
+
+use super::{dimension_hash, hash_to_f32, permute, SampleGenerator};
+
+/// Pattern of the given sample, every round of cell_count samples uses a new pattern
+fn pattern(
+    seed: u32,
+    pixel_index: u32,
+    sample_number: u32,
+    dimension: u32,
+    cell_count: u32,
+) -> u32 {
+    dimension_hash(seed, pixel_index, dimension).wrapping_add(sample_number / cell_count)
+}
+
+/// One random value in every one of the cell_count intervals of [0, 1), visited in random order
+fn stratified_1d(sample_number: u32, cell_count: u32, pattern: u32) -> f32 {
+    let cell = permute(sample_number % cell_count, cell_count, pattern);
+    (cell as f32 + hash_to_f32(sample_number, pattern.wrapping_mul(0x68bc21eb))) / cell_count as f32
+}
+
+/// One random sample in every cell of a grid with samples_per_side cells per side
+/// Every pixel and dimension visits the cells in its own random order, so partial progressive renders stay spread out
+/// Single dimensions are split into samples_per_side squared intervals
+pub struct JitteredSampler {
+    samples_per_side: u32,
+    seed: u32,
+}
+
+impl JitteredSampler {
+    pub fn new(samples_per_side: u32, seed: u32) -> Self {
+        assert!(samples_per_side > 0);
+
+        Self {
+            samples_per_side,
+            seed,
+        }
+    }
+}
+
+impl SampleGenerator for JitteredSampler {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        let cell_count = self.samples_per_side * self.samples_per_side;
+        let pattern = pattern(self.seed, pixel_index, sample_number, dimension, cell_count);
+        stratified_1d(sample_number, cell_count, pattern)
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        let cell_count = self.samples_per_side * self.samples_per_side;
+        let pattern = pattern(self.seed, pixel_index, sample_number, dimension, cell_count);
+
+        let cell = permute(sample_number % cell_count, cell_count, pattern);
+        let column = cell % self.samples_per_side;
+        let row = cell / self.samples_per_side;
+
+        let x = (column as f32 + hash_to_f32(sample_number, pattern.wrapping_mul(0xa399d265)))
+            / self.samples_per_side as f32;
+        let y = (row as f32 + hash_to_f32(sample_number, pattern.wrapping_mul(0x711ad6a5)))
+            / self.samples_per_side as f32;
+
+        Vec2::new(x, y)
+    }
+
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::Jittered {
+            samples_per_side: self.samples_per_side,
+            seed: self.seed,
+        })
+    }
+}
+
+/// Correlated multi-jittered samples by Kensler, jittered samples that are also stratified
+/// along both axes separately, so each row and column of the fine grid holds exactly one sample
+/// Single dimensions are split into samples_per_side squared intervals
+pub struct MultiJitteredSampler {
+    samples_per_side: u32,
+    seed: u32,
+}
+
+impl MultiJitteredSampler {
+    pub fn new(samples_per_side: u32, seed: u32) -> Self {
+        assert!(samples_per_side > 0);
+
+        Self {
+            samples_per_side,
+            seed,
+        }
+    }
+}
+
+impl SampleGenerator for MultiJitteredSampler {
+    fn get_1d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> f32 {
+        let cell_count = self.samples_per_side * self.samples_per_side;
+        let pattern = pattern(self.seed, pixel_index, sample_number, dimension, cell_count);
+        stratified_1d(sample_number, cell_count, pattern)
+    }
+
+    fn get_2d(&self, pixel_index: u32, sample_number: u32, dimension: u32) -> Vec2 {
+        let n = self.samples_per_side;
+        let cell_count = n * n;
+        let pattern = pattern(self.seed, pixel_index, sample_number, dimension, cell_count);
+
+        let s = permute(
+            sample_number % cell_count,
+            cell_count,
+            pattern.wrapping_mul(0x51633e2d),
+        );
+        let sx = permute(s % n, n, pattern.wrapping_mul(0xa511e9b3));
+        let sy = permute(s / n, n, pattern.wrapping_mul(0x63d83595));
+        let jx = hash_to_f32(s, pattern.wrapping_mul(0xa399d265));
+        let jy = hash_to_f32(s, pattern.wrapping_mul(0x711ad6a5));
+
+        let x = ((s % n) as f32 + (sy as f32 + jx) / n as f32) / n as f32;
+        let y = ((s / n) as f32 + (sx as f32 + jy) / n as f32) / n as f32;
+
+        Vec2::new(x, y)
+    }
+
+    fn describe(&self) -> Option<SamplerDescription> {
+        Some(SamplerDescription::MultiJittered {
+            samples_per_side: self.samples_per_side,
+            seed: self.seed,
+        })
+    }
+}
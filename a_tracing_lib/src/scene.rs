@@ -1,110 +1,187 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
 pub mod camera;
+pub mod description;
+pub mod disk;
 pub mod light;
+pub mod material;
+pub mod mesh;
 pub mod object;
+pub mod rectangle;
 pub mod sphere;
+pub mod texture;
+pub mod triangle;
 
 use crate::{
     color::{self, Color},
     ray::{HitRecord, Ray},
 };
 use glam::Vec3;
+use std::{f32::consts::PI, sync::OnceLock};
 
 use self::{
+    background::Background,
+    bvh::Bvh,
     camera::PerspectiveCamera,
-    light::{ambient_light::AmbientLight, DirectionalLight, Light},
+    light::{ambient_light::AmbientLight, AreaLight, DirectionalLight, EnvironmentLight, Light},
+    material::{Lambertian, Phong},
     object::Object,
     sphere::Sphere,
 };
 
+/// Rays ignore hits closer than this distance to avoid hitting the surface they start on
+pub(crate) const HIT_EPSILON: f32 = 0.0005;
+
 pub struct Scene {
     pub camera: PerspectiveCamera,
+    /// Seen by rays leaving the scene, an environment map also lights it
+    pub background: Background,
     objects: Vec<Object>,
     lights: Vec<Box<dyn Light>>,
+    /// Lights sampling the surface of emissive objects, kept apart so their object indices follow removals
+    area_lights: Vec<AreaLight>,
+    /// Hierarchy over the objects, built on the first intersection test after the objects were edited
+    bvh: OnceLock<Bvh>,
 }
 
 impl Scene {
     pub fn new(camera: PerspectiveCamera) -> Self {
         Self {
             camera,
+            background: Background::default(),
             objects: Vec::new(),
             lights: Vec::new(),
+            area_lights: Vec::new(),
+            bvh: OnceLock::new(),
         }
     }
 
     pub fn add_object(&mut self, object: Object) {
         self.objects.push(object);
+        self.bvh = OnceLock::new();
     }
 
-    pub fn add_light<T: Light + 'static>(&mut self, light: T) {
-        self.lights.push(Box::new(light));
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
     }
 
-    pub fn first_hit(&self, ray: &Ray) -> Option<(&Object, HitRecord)> {
-        let mut t_min = f32::MAX;
-        let mut result = None;
-
-        for object in self.objects.iter() {
-            if let Some(record) = object.shape.hit(ray, 0.0005, t_min) {
-                if record.t < t_min {
-                    t_min = record.t;
-                    result = Some((object, record));
-                }
+    /// Get mutable access to an object, the acceleration structure is rebuilt afterwards
+    pub fn object_mut(&mut self, index: usize) -> Option<&mut Object> {
+        self.bvh = OnceLock::new();
+        self.objects.get_mut(index)
+    }
+
+    /// Remove an object, an area light sampling it is removed as well
+    pub fn remove_object(&mut self, index: usize) -> Object {
+        self.bvh = OnceLock::new();
+
+        self.area_lights
+            .retain(|light| light.object_index() != index);
+        for light in &mut self.area_lights {
+            if light.object_index() > index {
+                light.set_object_index(light.object_index() - 1);
             }
         }
 
-        result
+        self.objects.remove(index)
     }
 
-    pub fn any_hit(&self, ray: &Ray) -> Option<HitRecord> {
-        let t_min = f32::MAX;
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<_> = self
+                .objects
+                .iter()
+                .map(|object| object.shape.bounds())
+                .collect();
+            Bvh::build(&bounds)
+        })
+    }
 
-        for object in self.objects.iter() {
-            if let Some(record) = object.shape.hit(ray, 0.0005, t_min) {
-                if record.t < t_min {
-                    return Some(record);
-                }
-            }
-        }
+    pub fn add_light<T: Light + 'static>(&mut self, light: T) {
+        self.lights.push(Box::new(light));
+    }
 
-        None
+    pub fn add_boxed_light(&mut self, light: Box<dyn Light>) {
+        self.lights.push(light);
     }
 
-    pub fn trace_ray(&self, ray: &Ray) -> Color {
-        let hit = self.first_hit(ray);
-        if let Some((object, record)) = hit {
-            let mut color_sum = color::BLACK;
+    /// Add an emissive object whose surface is sampled as a light, giving soft shadows
+    /// sample_count is the number of points on the surface sampled per shading point
+    pub fn add_area_light(&mut self, object: Object, sample_count: u32) {
+        self.add_object(object);
+        self.set_area_light(self.objects.len() - 1, sample_count);
+    }
 
-            for light in self.lights.iter() {
-                if let Some(light_ray) = light.light_at(self, record.point) {
-                    let camera_dir = (self.camera.origin - record.point).normalize();
-                    color_sum += object.shade(
-                        record.normal,
-                        light_ray.color,
-                        light_ray.direction,
-                        camera_dir,
-                    );
-                }
-            }
+    /// Sample the surface of an existing object as a light, replacing the sample count if it already is one
+    /// The shape of the object has to support ```sample_surface``` and its material should emit light
+    pub fn set_area_light(&mut self, object_index: usize, sample_count: u32) {
+        assert!(
+            object_index < self.objects.len(),
+            "Area light object index out of range."
+        );
+
+        self.area_lights
+            .retain(|light| light.object_index() != object_index);
+        self.area_lights
+            .push(AreaLight::new(object_index, sample_count));
+    }
 
-            color_sum
-        } else {
-            self.background_color(&ray.direction)
-        }
+    /// The area light sampling the object at the index, if it is one
+    pub fn area_light(&self, object_index: usize) -> Option<&AreaLight> {
+        self.area_lights
+            .iter()
+            .find(|light| light.object_index() == object_index)
     }
 
-    pub fn first_hit_color(&self, ray: &Ray) -> Color {
-        let hit = self.first_hit(ray);
-        if let Some((object, _record)) = hit {
-            object.color
-        } else {
-            self.background_color(&ray.direction)
-        }
+    /// Returns true if the object at the index is sampled as an area light
+    pub fn is_area_light(&self, object_index: usize) -> bool {
+        self.area_light(object_index).is_some()
+    }
+
+    pub fn lights(&self) -> impl Iterator<Item = &dyn Light> {
+        self.lights
+            .iter()
+            .map(|light| light.as_ref())
+            .chain(self.area_lights.iter().map(|light| light as &dyn Light))
+    }
+
+    /// Every light sampled for direct lighting, the lights followed by the background if it is sampled
+    pub fn sampled_lights(&self) -> impl Iterator<Item = &dyn Light> {
+        let environment = self
+            .background
+            .is_sampled()
+            .then_some(&EnvironmentLight as &dyn Light);
+
+        self.lights().chain(environment)
+    }
+
+    pub fn first_hit(&self, ray: &Ray) -> Option<(&Object, HitRecord)> {
+        self.first_hit_index(ray)
+            .map(|(index, record)| (&self.objects[index], record))
+    }
+
+    /// Like ```first_hit``` but returns the index of the hit object in ```objects```
+    pub fn first_hit_index(&self, ray: &Ray) -> Option<(usize, HitRecord)> {
+        self.bvh()
+            .closest_hit(ray, HIT_EPSILON, f32::MAX, |index, t_max| {
+                self.objects[index]
+                    .shape
+                    .hit(ray, HIT_EPSILON, t_max)
+                    .map(|record| (record.t, (index, record)))
+            })
+    }
+
+    /// Find any hit along the ray before t_max, used for shadow rays that only need to know whether something is in the way
+    /// t_max is measured in units of the ray direction, rays towards a point should stop at the distance to it
+    pub fn any_hit(&self, ray: &Ray, t_max: f32) -> Option<HitRecord> {
+        self.bvh().any_hit(ray, HIT_EPSILON, t_max, |index| {
+            self.objects[index].shape.hit(ray, HIT_EPSILON, t_max)
+        })
     }
 
     pub fn background_color(&self, direction: &Vec3) -> Color {
-        let normalized = direction.normalize();
-        let t = 0.5 * (normalized.y + 1.0);
-        color::SKYBLUE.lerp(color::WHITE, t)
+        self.background.color(*direction)
     }
 }
 
@@ -117,7 +194,7 @@ impl Default for Scene {
                 center: Vec3::new(0.0, 0.0, 0.0),
                 radius: 0.5,
             },
-            color::RED,
+            Phong::new(color::RED * 0.8, color::WHITE * 0.2, 32.0),
         ));
 
         scene.add_object(Object::new(
@@ -125,11 +202,11 @@ impl Default for Scene {
                 center: Vec3::new(0.0, -100.5, 0.0),
                 radius: 100.0,
             },
-            color::BLUE,
+            Lambertian::new(color::BLUE * 0.8),
         ));
 
         scene.add_light(DirectionalLight::new(
-            color::WHITE,
+            color::WHITE * PI,
             Vec3::new(0.1, -0.7, -0.2),
         ));
 
@@ -0,0 +1,135 @@
+use glam::Vec3;
+
+use crate::ray::Ray;
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Box that contains nothing, the identity for ```union```
+    pub fn empty() -> Self {
+        Self {
+            min: Vec3::splat(f32::INFINITY),
+            max: Vec3::splat(f32::NEG_INFINITY),
+        }
+    }
+
+    /// Smallest box containing all given points
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |aabb, point| aabb.grow(point))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Smallest box containing the box and the point
+    pub fn grow(&self, point: Vec3) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        (self.max - self.min).max(Vec3::ZERO)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let extent = self.extent();
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    /// Index of the axis along which the box is the largest
+    pub fn largest_axis(&self) -> usize {
+        let extent = self.extent();
+
+        if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test, returns true if the ray passes through the box between t_min and t_max
+    pub fn hit(&self, ray: &Ray, inverse_direction: Vec3, t_min: f32, t_max: f32) -> bool {
+        let t0 = (self.min - ray.origin) * inverse_direction;
+        let t1 = (self.max - ray.origin) * inverse_direction;
+
+        let near = t0.min(t1).max_element().max(t_min);
+        let far = t0.max(t1).min_element().min(t_max);
+
+        near <= far
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::ray::Ray;
+
+    use super::Aabb;
+
+    #[test]
+    fn hit_test() {
+        let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::ONE);
+        let ray = Ray {
+            origin: Vec3::new(0.5, 0.5, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        assert!(aabb.hit(&ray, ray.direction.recip(), 0.0, f32::MAX));
+        assert!(!aabb.hit(&ray, ray.direction.recip(), 0.0, 3.0));
+    }
+
+    #[test]
+    fn miss_test() {
+        let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::ONE);
+        let ray = Ray {
+            origin: Vec3::new(2.0, 0.0, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        assert!(!aabb.hit(&ray, ray.direction.recip(), 0.0, f32::MAX));
+    }
+
+    #[test]
+    fn flat_box_test() {
+        let aabb = Aabb::from_points([Vec3::ZERO, Vec3::new(1.0, 1.0, 0.0)]);
+        let ray = Ray {
+            origin: Vec3::new(0.5, 0.5, 1.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        assert!(aabb.hit(&ray, ray.direction.recip(), 0.0, f32::MAX));
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    f32::consts::{PI, TAU},
+    path::{Path, PathBuf},
+};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    color::Color,
+    image::{self, ImageError},
+    sampling::Distribution1D,
+};
+
+/// Equirectangular image of the light arriving from every direction
+/// The center of the image faces positive z, the top row looks straight up
+/// Directions are picked with a probability proportional to the brightness of the texels they fall in
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    /// Rotation around the y axis in radians
+    rotation: f32,
+    /// Multiplier of the texel colors
+    intensity: f32,
+    /// File the image was loaded from, needed to save the map to a scene file
+    path: Option<PathBuf>,
+    /// Distribution of the rows, weighted by their total brightness
+    rows: Distribution1D,
+    /// Distribution of the texels within every row
+    columns: Vec<Distribution1D>,
+}
+
+impl EnvironmentMap {
+    /// Create a map from texels in row-major order from the top row down
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        assert!(
+            width > 0 && height > 0 && texels.len() == width * height,
+            "Environment map size doesn't match the number of texels."
+        );
+
+        // Rows near the poles cover a smaller solid angle
+        let mut row_weights = Vec::with_capacity(height);
+        let columns = texels
+            .chunks_exact(width)
+            .enumerate()
+            .map(|(row, texels)| {
+                let sin_theta = ((row as f32 + 0.5) / height as f32 * PI).sin();
+                let weights: Vec<f32> = texels
+                    .iter()
+                    .map(|texel| texel.luminance().max(0.0) * sin_theta)
+                    .collect();
+
+                row_weights.push(weights.iter().sum());
+                Distribution1D::new(weights)
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            texels,
+            rotation: 0.0,
+            intensity: 1.0,
+            path: None,
+            rows: Distribution1D::new(row_weights),
+            columns,
+        }
+    }
+
+    /// Load a Radiance HDR image
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        let image = image::read_hdr(path)?;
+
+        let mut map = Self::new(image.width, image.height, image.colors);
+        map.path = Some(path.to_owned());
+        Ok(map)
+    }
+
+    /// Rotate the map around the y axis by the angle in radians
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn color(&self, direction: Vec3) -> Color {
+        let (x, y) = self.texel_of(self.direction_to_uv(direction));
+        self.texels[y * self.width + x] * self.intensity
+    }
+
+    /// Returns the normalized direction and its pdf with respect to solid angle
+    pub fn sample_direction(&self, sample: Vec2) -> Option<(Vec3, f32)> {
+        let (v, row, row_pdf) = self.rows.sample(sample.y);
+        let (u, _, column_pdf) = self.columns[row].sample(sample.x);
+
+        let theta = v * PI;
+        let sin_theta = theta.sin();
+        let pdf = row_pdf * column_pdf / (2.0 * PI * PI * sin_theta);
+        if pdf <= 0.0 || !pdf.is_finite() {
+            return None;
+        }
+
+        Some((self.uv_to_direction(Vec2::new(u, v)), pdf))
+    }
+
+    /// Pdf with respect to solid angle of ```sample_direction``` returning the direction
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let uv = self.direction_to_uv(direction);
+        let (x, y) = self.texel_of(uv);
+
+        let sin_theta = (uv.y * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        self.rows.pdf(y) * self.columns[y].pdf(x) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn direction_to_uv(&self, direction: Vec3) -> Vec2 {
+        let direction = direction.normalize();
+        let phi = direction.x.atan2(direction.z) - self.rotation;
+
+        Vec2::new(
+            (0.5 + phi / TAU).rem_euclid(1.0),
+            direction.y.clamp(-1.0, 1.0).acos() / PI,
+        )
+    }
+
+    fn uv_to_direction(&self, uv: Vec2) -> Vec3 {
+        let phi = (uv.x - 0.5) * TAU + self.rotation;
+        let theta = uv.y * PI;
+
+        Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            theta.sin() * phi.cos(),
+        )
+    }
+
+    fn texel_of(&self, uv: Vec2) -> (usize, usize) {
+        (
+            ((uv.x * self.width as f32) as usize).min(self.width - 1),
+            ((uv.y * self.height as f32) as usize).min(self.height - 1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{Vec2, Vec3};
+
+    use crate::color::{self, Color};
+
+    use super::EnvironmentMap;
+
+    /// A dark map with a single bright texel
+    fn spot_map() -> EnvironmentMap {
+        let mut texels = vec![Color::new(0.01, 0.01, 0.01); 8 * 4];
+        texels[8 + 6] = color::WHITE * 100.0;
+        EnvironmentMap::new(8, 4, texels)
+    }
+
+    #[test]
+    fn lookup_and_rotation() {
+        let map = spot_map();
+        // Column 6 of 8 is centered at u = 0.8125, a quarter turn past positive x
+        let bright = map.uv_to_direction(Vec2::new(0.8125, 0.375));
+        assert_eq!(map.color(bright), color::WHITE * 100.0);
+        assert_eq!(map.color(Vec3::Z), Color::new(0.01, 0.01, 0.01));
+
+        let rotated = spot_map().with_rotation(FRAC_PI_2).with_intensity(2.0);
+        let turned = Vec3::new(bright.z, bright.y, -bright.x);
+        assert_eq!(rotated.color(turned), color::WHITE * 200.0);
+    }
+
+    #[test]
+    fn samples_favor_bright_texels() {
+        let map = spot_map();
+        let mut bright_samples = 0;
+
+        for i in 0..64 {
+            let sample = Vec2::new((i % 8) as f32 / 8.0 + 0.01, (i / 8) as f32 / 8.0 + 0.01);
+            let (direction, pdf) = map.sample_direction(sample).unwrap();
+
+            assert!((direction.length() - 1.0).abs() < 1e-4);
+            assert!((pdf - map.pdf(direction)).abs() <= pdf * 1e-3);
+            if map.color(direction).r > 1.0 {
+                bright_samples += 1;
+            }
+        }
+
+        assert!(bright_samples > 48);
+    }
+}
@@ -0,0 +1,91 @@
+pub mod environment_map;
+pub mod sky;
+
+use glam::{Vec2, Vec3};
+
+use super::description::{BackgroundDescription, SunDescription};
+use crate::color::{self, Color};
+pub use environment_map::EnvironmentMap;
+pub use sky::PreethamSky;
+
+/// Light arriving from directions in which a ray leaves the scene without hitting anything
+pub enum Background {
+    /// The same color in every direction
+    Color(Color),
+    /// Blend from the bottom color straight down to the top color straight up
+    Gradient { bottom: Color, top: Color },
+    /// Equirectangular image around the scene, importance sampled as a light
+    Environment(EnvironmentMap),
+    /// Analytic clear sky whose sun disk and sky are sampled as a light
+    Sky(PreethamSky),
+}
+
+impl Background {
+    /// Color seen along the direction, which doesn't have to be normalized
+    pub fn color(&self, direction: Vec3) -> Color {
+        match self {
+            Background::Color(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.normalize().y + 1.0);
+                bottom.lerp(*top, t)
+            }
+            Background::Environment(map) => map.color(direction),
+            Background::Sky(sky) => sky.color(direction),
+        }
+    }
+
+    /// Returns true if the background is sampled as a light, so paths shouldn't add it after a diffuse bounce
+    pub fn is_sampled(&self) -> bool {
+        matches!(self, Background::Environment(_) | Background::Sky(_))
+    }
+
+    /// Pick a direction favoring the bright parts of the background, such as the sun
+    /// Returns the normalized direction and its pdf with respect to solid angle, None if the background isn't sampled
+    pub fn sample_direction(&self, sample: Vec2) -> Option<(Vec3, f32)> {
+        match self {
+            Background::Environment(map) => map.sample_direction(sample),
+            Background::Sky(sky) => sky.sample_direction(sample),
+            Background::Color(_) | Background::Gradient { .. } => None,
+        }
+    }
+
+    /// Solid angle pdf of ```sample_direction``` returning the direction, zero if the background isn't sampled
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        match self {
+            Background::Environment(map) => map.pdf(direction),
+            Background::Sky(sky) => sky.pdf(direction),
+            Background::Color(_) | Background::Gradient { .. } => 0.0,
+        }
+    }
+
+    /// Description used to save the background to a scene file, environment maps not loaded from a file can't be saved
+    pub fn describe(&self) -> Option<BackgroundDescription> {
+        match self {
+            Background::Color(color) => Some(BackgroundDescription::Color { color: *color }),
+            Background::Gradient { bottom, top } => Some(BackgroundDescription::Gradient {
+                bottom: *bottom,
+                top: *top,
+            }),
+            Background::Environment(map) => Some(BackgroundDescription::Environment {
+                path: map.path()?.to_owned(),
+                rotation_degrees: map.rotation().to_degrees(),
+                intensity: map.intensity(),
+            }),
+            Background::Sky(sky) => Some(BackgroundDescription::Sky {
+                sun: SunDescription::Direction(sky.sun_direction()),
+                turbidity: sky.turbidity(),
+                intensity: sky.intensity(),
+            }),
+        }
+    }
+}
+
+impl Default for Background {
+    /// Sky blue towards the bottom fading to white towards the top
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: color::SKYBLUE,
+            top: color::WHITE,
+        }
+    }
+}
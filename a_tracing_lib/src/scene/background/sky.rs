@@ -0,0 +1,319 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    color::{self, Color},
+    sampling::{uniform_sample_cone, uniform_sample_sphere},
+};
+
+/// Angular radius of the sun disk seen from the earth in radians
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+
+/// 1 - cos of the angular radius, the small angle approximation keeps the precision
+const SUN_ONE_MINUS_COS: f32 = SUN_ANGULAR_RADIUS * SUN_ANGULAR_RADIUS / 2.0;
+
+/// Luminance of the sun outside of the atmosphere in kcd/m²
+const SUN_LUMINANCE: f32 = 2.0e6;
+
+/// Render units per kcd/m², puts a clear zenith around a third
+const LUMINANCE_SCALE: f32 = 0.05;
+
+/// Probability of sampling the sun disk instead of the whole sky while the sun is up
+const SUN_SAMPLE_PROBABILITY: f32 = 0.5;
+
+/// Wavelengths in micrometers used for the red, green and blue extinction of sunlight
+const WAVELENGTHS: [f32; 3] = [0.65, 0.57, 0.475];
+
+/// Preetham analytic daylight model of a clear sky with the sun disk in it
+/// Driven by the direction towards the sun and the turbidity, the haziness of the atmosphere
+/// Directions below the horizon get the color of the sky at the horizon
+#[derive(Debug, Clone)]
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    turbidity: f32,
+    intensity: f32,
+    /// Perez coefficients A to E of the luminance Y and the chromaticities x and y
+    perez: [[f32; 5]; 3],
+    /// Zenith values of Y, x and y divided by the Perez function towards the zenith
+    zenith_scale: [f32; 3],
+    /// Radiance of the sun disk after passing through the atmosphere
+    sun_radiance: Color,
+}
+
+impl PreethamSky {
+    /// sun_direction points from the scene towards the sun, turbidity is clamped to the valid range of 1.7 to 10
+    pub fn new(sun_direction: Vec3, turbidity: f32) -> Self {
+        let mut sky = Self {
+            sun_direction: sun_direction.normalize(),
+            turbidity: turbidity.clamp(1.7, 10.0),
+            intensity: 1.0,
+            perez: [[0.0; 5]; 3],
+            zenith_scale: [0.0; 3],
+            sun_radiance: color::BLACK,
+        };
+        sky.update();
+        sky
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    pub fn set_sun_direction(&mut self, sun_direction: Vec3) {
+        self.sun_direction = sun_direction.normalize();
+        self.update();
+    }
+
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    pub fn set_turbidity(&mut self, turbidity: f32) {
+        self.turbidity = turbidity.clamp(1.7, 10.0);
+        self.update();
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    /// Recompute the coefficients after the sun or turbidity changed
+    fn update(&mut self) {
+        let t = self.turbidity;
+        // The model isn't defined for the sun below the horizon, keep the twilight of a setting sun
+        let theta_sun = self.sun_direction.y.clamp(-1.0, 1.0).acos().min(FRAC_PI_2);
+
+        self.perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let cubic = |coefficients: [f32; 4]| {
+            ((coefficients[0] * theta_sun + coefficients[1]) * theta_sun + coefficients[2])
+                * theta_sun
+                + coefficients[3]
+        };
+        let zenith_x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        for (index, zenith) in [zenith_luminance, zenith_x, zenith_y]
+            .into_iter()
+            .enumerate()
+        {
+            self.zenith_scale[index] = zenith / perez(&self.perez[index], 1.0, theta_sun);
+        }
+
+        self.sun_radiance = self.sun_transmittance() * (SUN_LUMINANCE * LUMINANCE_SCALE);
+    }
+
+    /// Fraction of the red, green and blue sunlight reaching the ground through Rayleigh and aerosol scattering
+    fn sun_transmittance(&self) -> Color {
+        let cos_theta = self.sun_direction.y;
+        if cos_theta <= 0.0 {
+            return color::BLACK;
+        }
+
+        // Relative optical mass of the air the light travels through
+        let theta_degrees = cos_theta.acos().to_degrees();
+        let mass = 1.0 / (cos_theta + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+
+        let [r, g, b] = WAVELENGTHS.map(|wavelength| {
+            let rayleigh = (-0.008735 * wavelength.powf(-4.08) * mass).exp();
+            let aerosol = (-beta * wavelength.powf(-1.3) * mass).exp();
+            rayleigh * aerosol
+        });
+        Color::new(r, g, b)
+    }
+
+    pub fn color(&self, direction: Vec3) -> Color {
+        let direction = direction.normalize();
+        let sky = self.sky_color(direction);
+
+        if self.in_sun_disk(direction) {
+            sky + self.sun_radiance * self.intensity
+        } else {
+            sky
+        }
+    }
+
+    fn sky_color(&self, direction: Vec3) -> Color {
+        let cos_theta = direction.y.max(0.0);
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|index| self.zenith_scale[index] * perez(&self.perez[index], cos_theta, gamma));
+        if luminance <= 0.0 || y <= 0.0 {
+            return color::BLACK;
+        }
+
+        let (cie_x, cie_y, cie_z) = (x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let scale = LUMINANCE_SCALE * self.intensity;
+
+        Color::new(
+            (3.2406 * cie_x - 1.5372 * cie_y - 0.4986 * cie_z).max(0.0) * scale,
+            (-0.9689 * cie_x + 1.8758 * cie_y + 0.0415 * cie_z).max(0.0) * scale,
+            (0.0557 * cie_x - 0.2040 * cie_y + 1.0570 * cie_z).max(0.0) * scale,
+        )
+    }
+
+    fn in_sun_disk(&self, direction: Vec3) -> bool {
+        1.0 - direction.dot(self.sun_direction) <= SUN_ONE_MINUS_COS
+    }
+
+    fn sun_probability(&self) -> f32 {
+        if self.sun_radiance.max_component() > 0.0 {
+            SUN_SAMPLE_PROBABILITY
+        } else {
+            0.0
+        }
+    }
+
+    /// Sample the sun disk half of the time while the sun is up and the whole sphere otherwise
+    /// Returns the normalized direction and its pdf with respect to solid angle
+    pub fn sample_direction(&self, sample: Vec2) -> Option<(Vec3, f32)> {
+        let sun_probability = self.sun_probability();
+
+        let direction = if sample.x < sun_probability {
+            let sample = Vec2::new(sample.x / sun_probability, sample.y);
+            uniform_sample_cone(self.sun_direction, SUN_ONE_MINUS_COS, sample)
+        } else {
+            let remapped = (sample.x - sun_probability) / (1.0 - sun_probability);
+            uniform_sample_sphere(Vec2::new(remapped.min(1.0), sample.y))
+        };
+
+        Some((direction, self.pdf(direction)))
+    }
+
+    /// Pdf with respect to solid angle of ```sample_direction``` returning the direction
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let sun_probability = self.sun_probability();
+        let sphere_pdf = (1.0 - sun_probability) / (2.0 * TAU);
+
+        if self.in_sun_disk(direction.normalize()) {
+            sphere_pdf + sun_probability / (TAU * SUN_ONE_MINUS_COS)
+        } else {
+            sphere_pdf
+        }
+    }
+}
+
+/// Perez sky distribution function for a direction at zenith angle theta and angle gamma from the sun
+fn perez(coefficients: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+    let cos_gamma = gamma.cos();
+
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+/// Direction towards the sun with y up, x east and z north
+/// day_of_year counts from 1 on the first of January, solar_hour is 12 when the sun is highest,
+/// latitude is in radians and positive on the northern hemisphere
+pub fn sun_direction(day_of_year: u32, solar_hour: f32, latitude: f32) -> Vec3 {
+    let declination = 23.44f32.to_radians() * (TAU * (284 + day_of_year) as f32 / 365.0).sin();
+    let hour_angle = (solar_hour - 12.0) * 15f32.to_radians();
+
+    Vec3::new(
+        -declination.cos() * hour_angle.sin(),
+        latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos(),
+        latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * hour_angle.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use super::{sun_direction, PreethamSky};
+
+    #[test]
+    fn zenith_is_bluer_than_horizon() {
+        let sky = PreethamSky::new(Vec3::new(0.0, 1.0, 1.0), 3.0);
+        let zenith = sky.color(Vec3::Y);
+        let horizon = sky.color(Vec3::new(0.0, 0.05, -1.0));
+
+        assert!(zenith.b > zenith.r);
+        assert!(horizon.b / horizon.r < zenith.b / zenith.r);
+        assert!((0.1..2.0).contains(&zenith.luminance()));
+        assert!(sky.color(Vec3::new(0.0, 1.0, 1.0)).luminance() > 1000.0);
+    }
+
+    #[test]
+    fn setting_sun_is_redder() {
+        let high = PreethamSky::new(Vec3::Y, 3.0).sun_radiance;
+        let low = PreethamSky::new(Vec3::new(0.0, 0.05, 1.0), 3.0).sun_radiance;
+        let set = PreethamSky::new(Vec3::new(0.0, -0.1, 1.0), 3.0).sun_radiance;
+
+        assert!(low.r / low.b > high.r / high.b);
+        assert!(low.g < high.g);
+        assert_eq!(set.max_component(), 0.0);
+    }
+
+    #[test]
+    fn samples_hit_the_sun() {
+        let sky = PreethamSky::new(Vec3::new(1.0, 1.0, 0.0), 4.0);
+        let mut sun_samples = 0;
+
+        for i in 0..16 {
+            let sample = Vec2::new(i as f32 / 16.0 + 0.01, 0.3);
+            let (direction, pdf) = sky.sample_direction(sample).unwrap();
+
+            assert!((pdf - sky.pdf(direction)).abs() <= pdf * 1e-4);
+            if sky.in_sun_disk(direction) {
+                sun_samples += 1;
+            }
+        }
+
+        assert_eq!(sun_samples, 8);
+    }
+
+    #[test]
+    fn sun_position() {
+        // Around the equinox the sun is overhead at noon on the equator and rises in the east
+        let noon = sun_direction(80, 12.0, 0.0);
+        assert!(noon.y > 0.99);
+
+        let morning = sun_direction(80, 7.0, 0.0);
+        assert!(morning.x > 0.9 && morning.y > 0.0);
+
+        // Northern summer noon sun stands in the south
+        let summer = sun_direction(172, 12.0, 50f32.to_radians());
+        assert!(summer.z < 0.0 && summer.y > 0.8);
+    }
+}
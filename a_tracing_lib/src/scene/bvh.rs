@@ -0,0 +1,340 @@
+use glam::Vec3;
+
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+/// Number of buckets the centroids are binned in when evaluating the surface area heuristic
+const SAH_BUCKETS: usize = 12;
+
+/// Nodes with at most this many primitives are never split
+const MAX_LEAF_PRIMITIVES: usize = 4;
+
+/// Cost of traversing a node relative to intersecting a primitive
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// Number of nodes the traversal stack holds, it never holds more than one node per level plus one
+const STACK_SIZE: usize = 64;
+
+/// Nodes this deep are never split, which keeps the traversal stack from overflowing
+const MAX_DEPTH: usize = STACK_SIZE - 1;
+
+/// Bounding volume hierarchy over a list of primitives built with the surface area heuristic
+/// The hierarchy only stores primitive indices, intersecting the primitives themselves is up to the caller
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitive_indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bounds: Aabb,
+    /// First primitive of a leaf, or the left child of an interior node whose right child directly follows it
+    offset: u32,
+    /// Number of primitives in a leaf, 0 for interior nodes
+    count: u32,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bounds: Aabb,
+}
+
+impl Bvh {
+    /// Build a hierarchy over primitives with the given bounds
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * bounds.len()),
+            primitive_indices: (0..bounds.len() as u32).collect(),
+        };
+
+        if bounds.is_empty() {
+            return bvh;
+        }
+
+        let centroids: Vec<_> = bounds.iter().map(|aabb| aabb.centroid()).collect();
+
+        bvh.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            offset: 0,
+            count: bounds.len() as u32,
+        });
+        bvh.subdivide(0, 0, bounds, &centroids);
+
+        bvh
+    }
+
+    /// Bounds of everything in the hierarchy
+    pub fn bounds(&self) -> Aabb {
+        self.nodes
+            .first()
+            .map_or_else(Aabb::empty, |node| node.bounds)
+    }
+
+    fn subdivide(&mut self, node_index: usize, depth: usize, bounds: &[Aabb], centroids: &[Vec3]) {
+        let node = self.nodes[node_index];
+        let start = node.offset as usize;
+        let end = start + node.count as usize;
+
+        let primitives = &mut self.primitive_indices[start..end];
+        let node_bounds = primitives
+            .iter()
+            .fold(Aabb::empty(), |aabb, &i| aabb.union(&bounds[i as usize]));
+        self.nodes[node_index].bounds = node_bounds;
+
+        if primitives.len() <= MAX_LEAF_PRIMITIVES || depth >= MAX_DEPTH {
+            return;
+        }
+
+        let centroid_bounds = primitives
+            .iter()
+            .fold(Aabb::empty(), |aabb, &i| aabb.grow(centroids[i as usize]));
+        let axis = centroid_bounds.largest_axis();
+        let axis_min = centroid_bounds.min[axis];
+        let axis_extent = centroid_bounds.max[axis] - axis_min;
+
+        // All centroids coincide, splitting can't separate the primitives
+        if axis_extent <= 0.0 {
+            return;
+        }
+
+        let bucket_of = |i: u32| {
+            let relative = (centroids[i as usize][axis] - axis_min) / axis_extent;
+            ((relative * SAH_BUCKETS as f32) as usize).min(SAH_BUCKETS - 1)
+        };
+
+        let mut buckets = [Bucket {
+            count: 0,
+            bounds: Aabb::empty(),
+        }; SAH_BUCKETS];
+
+        for &i in primitives.iter() {
+            let bucket = &mut buckets[bucket_of(i)];
+            bucket.count += 1;
+            bucket.bounds = bucket.bounds.union(&bounds[i as usize]);
+        }
+
+        // Find the split between buckets with the lowest surface area heuristic cost
+        let (best_split, best_cost) = (1..SAH_BUCKETS)
+            .map(|split| {
+                let (left, right) = buckets.split_at(split);
+                let side_cost = |side: &[Bucket]| {
+                    let (count, aabb) = side.iter().fold((0, Aabb::empty()), |(count, aabb), b| {
+                        (count + b.count, aabb.union(&b.bounds))
+                    });
+                    count as f32 * aabb.surface_area()
+                };
+
+                let cost = TRAVERSAL_COST
+                    + (side_cost(left) + side_cost(right))
+                        / node_bounds.surface_area().max(f32::MIN_POSITIVE);
+                (split, cost)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("There is always more than one bucket.");
+
+        // Intersecting every primitive in a leaf is cheaper than splitting
+        if best_cost >= primitives.len() as f32 {
+            return;
+        }
+
+        // Partition the primitives in place around the chosen split
+        let mut left_count = 0;
+        for i in 0..primitives.len() {
+            if bucket_of(primitives[i]) < best_split {
+                primitives.swap(i, left_count);
+                left_count += 1;
+            }
+        }
+
+        // The best split can still leave one side empty when all primitives share a bucket
+        if left_count == 0 || left_count == primitives.len() {
+            left_count = primitives.len() / 2;
+            primitives.select_nth_unstable_by(left_count, |&a, &b| {
+                centroids[a as usize][axis].total_cmp(&centroids[b as usize][axis])
+            });
+        }
+
+        let left_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            offset: start as u32,
+            count: left_count as u32,
+        });
+        self.nodes.push(BvhNode {
+            bounds: Aabb::empty(),
+            offset: (start + left_count) as u32,
+            count: (end - start - left_count) as u32,
+        });
+
+        self.nodes[node_index].offset = left_index as u32;
+        self.nodes[node_index].count = 0;
+
+        self.subdivide(left_index, depth + 1, bounds, centroids);
+        self.subdivide(left_index + 1, depth + 1, bounds, centroids);
+    }
+
+    /// Find the closest primitive hit by the ray
+    /// hit is called with a primitive index and the current maximum distance,
+    /// and returns the distance and payload of the intersection if there is one
+    pub fn closest_hit<T>(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        mut hit: impl FnMut(usize, f32) -> Option<(f32, T)>,
+    ) -> Option<T> {
+        let mut closest = t_max;
+        let mut result = None;
+
+        self.traverse(ray, t_min, t_max, |primitive| {
+            if let Some((t, payload)) = hit(primitive, closest) {
+                if t < closest {
+                    closest = t;
+                    result = Some(payload);
+                }
+            }
+
+            Traversal::Continue(closest)
+        });
+
+        result
+    }
+
+    /// Find any primitive hit by the ray, stopping at the first one found
+    pub fn any_hit<T>(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        mut hit: impl FnMut(usize) -> Option<T>,
+    ) -> Option<T> {
+        let mut result = None;
+
+        self.traverse(ray, t_min, t_max, |primitive| match hit(primitive) {
+            Some(payload) => {
+                result = Some(payload);
+                Traversal::Stop
+            }
+            None => Traversal::Continue(t_max),
+        });
+
+        result
+    }
+
+    /// Visit every primitive in a leaf whose bounds are hit before the distance returned by the last visit
+    fn traverse(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        mut t_max: f32,
+        mut visit: impl FnMut(usize) -> Traversal,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inverse_direction = ray.direction.recip();
+        let mut stack = [0; STACK_SIZE];
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.nodes[stack[stack_len]];
+            if !node.bounds.hit(ray, inverse_direction, t_min, t_max) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                let start = node.offset as usize;
+                for &primitive in &self.primitive_indices[start..start + node.count as usize] {
+                    match visit(primitive as usize) {
+                        Traversal::Continue(distance) => t_max = distance,
+                        Traversal::Stop => return,
+                    }
+                }
+            } else {
+                stack[stack_len] = node.offset as usize + 1;
+                stack[stack_len + 1] = node.offset as usize;
+                stack_len += 2;
+            }
+        }
+    }
+}
+
+enum Traversal {
+    /// Keep looking for primitives closer than the given distance
+    Continue(f32),
+    Stop,
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::{
+        ray::Ray,
+        sampling::Rng,
+        scene::{object::Shape, sphere::Sphere},
+    };
+
+    use super::Bvh;
+
+    fn random_spheres(count: usize) -> Vec<Sphere> {
+        let mut rng = Rng::new(5);
+        (0..count)
+            .map(|_| {
+                let center = Vec3::new(rng.next_f32(), rng.next_f32(), rng.next_f32()) * 20.0;
+                Sphere::new(center - Vec3::splat(10.0), 0.2 + rng.next_f32())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn closest_hit_matches_linear_search() {
+        let spheres = random_spheres(200);
+        let bounds: Vec<_> = spheres.iter().map(|sphere| sphere.bounds()).collect();
+        let bvh = Bvh::build(&bounds);
+        let mut rng = Rng::new(9);
+
+        for _ in 0..200 {
+            let ray = Ray {
+                origin: Vec3::new(0.0, 0.0, -30.0),
+                direction: Vec3::new(rng.next_f32() - 0.5, rng.next_f32() - 0.5, 1.0),
+            };
+
+            let linear = spheres
+                .iter()
+                .filter_map(|sphere| sphere.hit(&ray, 0.0, f32::MAX))
+                .map(|record| record.t)
+                .min_by(f32::total_cmp);
+
+            let accelerated = bvh.closest_hit(&ray, 0.0, f32::MAX, |i, t_max| {
+                spheres[i]
+                    .hit(&ray, 0.0, t_max)
+                    .map(|record| (record.t, record.t))
+            });
+
+            assert_eq!(linear, accelerated);
+        }
+    }
+
+    #[test]
+    fn empty_bvh() {
+        let bvh = Bvh::build(&[]);
+        let ray = Ray {
+            origin: Vec3::ZERO,
+            direction: Vec3::Z,
+        };
+
+        assert!(bvh.any_hit(&ray, 0.0, f32::MAX, |_| Some(())).is_none());
+    }
+}
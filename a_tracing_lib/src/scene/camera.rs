@@ -1,8 +1,8 @@
 use std::f32::consts::FRAC_PI_3;
 
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 
-use crate::ray::Ray;
+use crate::{ray::Ray, sampling::concentric_sample_disk};
 
 pub struct PerspectiveCamera {
     pub origin: Vec3,
@@ -13,6 +13,10 @@ pub struct PerspectiveCamera {
     vertical: Vec3,
     vertical_fov: f32,
     aspect_ratio: f32,
+    /// Radius of the thin lens, zero gives a pinhole camera with everything in focus
+    aperture_radius: f32,
+    /// Distance along the viewing direction of the plane that is in focus
+    focus_distance: f32,
 }
 
 impl PerspectiveCamera {
@@ -41,13 +45,15 @@ impl PerspectiveCamera {
 
         Self {
             origin,
-            direction: direction,
+            direction,
             lower_left_corner,
             up,
             horizontal,
             vertical,
             vertical_fov,
             aspect_ratio,
+            aperture_radius: 0.0,
+            focus_distance: 1.0,
         }
     }
 
@@ -61,6 +67,28 @@ impl PerspectiveCamera {
         }
     }
 
+    /// Get a ray from the given horizontal and vertical values that starts at the given point on the lens
+    /// lens_sample is a point in the unit square, objects away from the focus distance get blurred
+    pub fn get_lens_ray(&self, h: f32, v: f32, lens_sample: Vec2) -> Ray {
+        let ray = self.get_ray(h, v);
+
+        if self.aperture_radius <= 0.0 {
+            return ray;
+        }
+
+        // The direction reaches the viewport at distance 1, so scaling it gives the point in focus
+        let focus_point = ray.origin + ray.direction * self.focus_distance;
+        let lens_point = self.aperture_radius * concentric_sample_disk(lens_sample);
+        let origin = self.origin
+            + lens_point.x * self.horizontal.normalize()
+            + lens_point.y * self.vertical.normalize();
+
+        Ray {
+            origin,
+            direction: focus_point - origin,
+        }
+    }
+
     fn recalculate_parameters(&mut self) {
         let h = (self.vertical_fov * 0.5).tan();
         let vp_height = 2.0 * h;
@@ -75,6 +103,37 @@ impl PerspectiveCamera {
         self.lower_left_corner = self.origin - 0.5 * self.horizontal - 0.5 * self.vertical - w;
     }
 
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.up
+    }
+
+    /// Vertical field of view in radians
+    pub fn vertical_fov(&self) -> f32 {
+        self.vertical_fov
+    }
+
+    pub fn aperture_radius(&self) -> f32 {
+        self.aperture_radius
+    }
+
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    /// Set the radius of the lens and the distance of the plane in focus, a radius of zero disables depth of field
+    pub fn set_lens(&mut self, aperture_radius: f32, focus_distance: f32) {
+        self.aperture_radius = aperture_radius.max(0.0);
+        self.focus_distance = focus_distance;
+    }
+
     /// Set the aspect ratio of the camera to the given value
     pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
         self.aspect_ratio = aspect_ratio;
@@ -160,3 +219,27 @@ fn rotate_vector(vector: Vec3, axis: Vec3, angle: f32) -> Vec3 {
 
     cos_vector + sin_vector + k_vector
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use super::PerspectiveCamera;
+
+    #[test]
+    fn lens_rays_meet_at_focus_distance() {
+        let mut camera = PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1.0, 1.0);
+        camera.set_lens(0.5, 4.0);
+
+        let center = camera.get_ray(0.3, 0.6);
+        let focus_point = center.origin + center.direction * 4.0;
+
+        for lens_sample in [Vec2::new(0.1, 0.2), Vec2::new(0.9, 0.5)] {
+            let ray = camera.get_lens_ray(0.3, 0.6, lens_sample);
+            let t = (focus_point.z - ray.origin.z) / ray.direction.z;
+
+            assert!(ray.origin.distance(Vec3::ZERO) > 0.0);
+            assert!((ray.origin + t * ray.direction).distance(focus_point) < 1e-4);
+        }
+    }
+}
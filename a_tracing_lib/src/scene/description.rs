@@ -0,0 +1,1381 @@
+use std::{
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+    ptr,
+    sync::Arc,
+    time::Duration,
+};
+
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::Color,
+    integrator::{
+        AmbientOcclusionIntegrator, DebugIntegrator, DebugMode, Integrator, PathTracer,
+        WhittedIntegrator,
+    },
+    sampling::{
+        HaltonSampler, JitteredSampler, MultiJitteredSampler, RandomSampler, RegularSampler,
+        SampleGenerator, SobolSampler,
+    },
+    tracer::{aov::Aov, RenderMode, RenderSettings, Resolution},
+};
+
+use super::{
+    background::{sky, Background, EnvironmentMap, PreethamSky},
+    camera::PerspectiveCamera,
+    disk::Disk,
+    light::{ambient_light::AmbientLight, DirectionalLight, Light, PointLight, SpotLight},
+    material::{Dielectric, Emissive, Lambertian, Material, Metal, PbrMaterial, Phong},
+    mesh::TriangleMesh,
+    object::{Object, Shape},
+    rectangle::Rectangle,
+    sphere::Sphere,
+    texture::Texture,
+    triangle::Triangle,
+    Scene,
+};
+
+/// Version of the scene file format written by ```Scene::save```
+/// Files with a newer version are rejected by ```Scene::load```
+pub const SCENE_FORMAT_VERSION: u32 = 1;
+
+/// Contents of a scene file, the scene and the settings to render it with
+/// Scene files are stored in the RON format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    pub version: u32,
+    pub camera: CameraDescription,
+    /// Materials shared by the objects, referenced by their index
+    #[serde(default)]
+    pub materials: Vec<MaterialDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+    #[serde(default)]
+    pub background: BackgroundDescription,
+    #[serde(default)]
+    pub render_settings: RenderSettingsDescription,
+}
+
+/// Only the version of a scene file, read before the rest so newer files get a clear error
+#[derive(Deserialize)]
+struct VersionDescription {
+    version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDescription {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub up: Vec3,
+    pub vertical_fov_degrees: f32,
+    /// Radius of the lens, zero or missing disables depth of field
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub aperture_radius: f32,
+    #[serde(default = "default_focus_distance")]
+    pub focus_distance: f32,
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn default_focus_distance() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectDescription {
+    pub shape: ShapeDescription,
+    /// Index into the materials of the scene description
+    pub material: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum ShapeDescription {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    Triangle {
+        vertices: [Vec3; 3],
+    },
+    Rectangle {
+        corner: Vec3,
+        edge_u: Vec3,
+        edge_v: Vec3,
+    },
+    Disk {
+        center: Vec3,
+        normal: Vec3,
+        radius: f32,
+    },
+    Mesh {
+        positions: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        normals: Vec<Vec3>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        uvs: Vec<Vec2>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian {
+        albedo: Color,
+    },
+    Phong {
+        diffuse: Color,
+        specular: Color,
+        exponent: f32,
+    },
+    Metal {
+        albedo: Color,
+        roughness: f32,
+    },
+    Dielectric {
+        ior: f32,
+        tint: Color,
+    },
+    Emissive {
+        color: Color,
+        strength: f32,
+    },
+    Pbr {
+        base_color: Color,
+        metallic: f32,
+        roughness: f32,
+        #[serde(default)]
+        emission: Color,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_color_texture: Option<TextureDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metallic_roughness_texture: Option<TextureDescription>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum TextureDescription {
+    /// PNG image, a relative path is relative to the scene file
+    /// Color textures usually store sRGB values, data such as roughness is usually stored linearly
+    File { path: PathBuf, srgb: bool },
+    /// Linear colors in row-major order, stored for textures that weren't loaded from a file such as embedded glTF images
+    Texels {
+        width: u32,
+        height: u32,
+        texels: Vec<Color>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum LightDescription {
+    Ambient {
+        color: Color,
+    },
+    Directional {
+        color: Color,
+        direction: Vec3,
+    },
+    Point {
+        intensity: Color,
+        position: Vec3,
+    },
+    Spot {
+        intensity: Color,
+        position: Vec3,
+        direction: Vec3,
+        inner_angle_degrees: f32,
+        outer_angle_degrees: f32,
+    },
+    /// Samples the surface of an emissive object
+    Area {
+        /// Index into the objects of the scene description
+        object: usize,
+        #[serde(default = "default_sample_count")]
+        sample_count: u32,
+    },
+}
+
+fn default_sample_count() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum BackgroundDescription {
+    Color {
+        color: Color,
+    },
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// Equirectangular Radiance HDR image, a relative path is relative to the scene file
+    Environment {
+        path: PathBuf,
+        #[serde(default)]
+        rotation_degrees: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+    /// Preetham clear sky with a sun disk, turbidity ranges from 1.7 for a very clear to 10 for a hazy sky
+    Sky {
+        sun: SunDescription,
+        #[serde(default = "default_turbidity")]
+        turbidity: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_turbidity() -> f32 {
+    3.0
+}
+
+/// Position of the sun in a sky background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum SunDescription {
+    /// Direction towards the sun with y up
+    Direction(Vec3),
+    /// Computed from the time with x east and z north, solar_hour is 12 when the sun is highest
+    Time {
+        day_of_year: u32,
+        solar_hour: f32,
+        latitude_degrees: f32,
+    },
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        Background::default()
+            .describe()
+            .expect("The default background can be described.")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderSettingsDescription {
+    pub resolution: Resolution,
+    pub number_of_samples: u32,
+    pub sampler: SamplerDescription,
+    pub render_mode: RenderModeDescription,
+    pub max_depth: u32,
+    pub integrator: IntegratorDescription,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aovs: Vec<Aov>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum SamplerDescription {
+    Regular {
+        samples_per_side: u32,
+    },
+    Random {
+        #[serde(default)]
+        seed: u32,
+    },
+    Jittered {
+        samples_per_side: u32,
+        #[serde(default)]
+        seed: u32,
+    },
+    MultiJittered {
+        samples_per_side: u32,
+        #[serde(default)]
+        seed: u32,
+    },
+    Halton {
+        #[serde(default)]
+        seed: u32,
+    },
+    Sobol {
+        #[serde(default)]
+        seed: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum RenderModeDescription {
+    Full,
+    Progressive {
+        #[serde(default)]
+        max_samples: Option<u32>,
+        #[serde(default)]
+        time_budget_seconds: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum IntegratorDescription {
+    PathTracer,
+    Whitted,
+    AmbientOcclusion { samples: u32, distance: f32 },
+    Debug { mode: DebugMode },
+}
+
+/// Error returned when loading or saving a scene file fails
+#[derive(Debug)]
+pub enum SceneFileError {
+    /// The file couldn't be read or written
+    Io { path: PathBuf, source: io::Error },
+    /// The file isn't a valid scene description
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    /// The scene description couldn't be converted to text
+    Serialize { path: PathBuf, source: ron::Error },
+    /// The file was written by a newer version of the format
+    UnsupportedVersion { path: PathBuf, version: u32 },
+    /// The scene contains a shape, material, light, sampler or integrator that has no description
+    NotDescribable { path: PathBuf, item: String },
+    /// The description parsed but contains invalid values, such as a material index that is out of range
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneFileError::Io { path, source } => {
+                write!(f, "{}: {source}", path.display())
+            }
+            SceneFileError::Parse { path, source } => {
+                write!(f, "{}:{source}", path.display())
+            }
+            SceneFileError::Serialize { path, source } => {
+                write!(f, "{}: serializing failed: {source}", path.display())
+            }
+            SceneFileError::UnsupportedVersion { path, version } => write!(
+                f,
+                "{}: scene format version {version} is newer than the supported version {SCENE_FORMAT_VERSION}",
+                path.display()
+            ),
+            SceneFileError::NotDescribable { path, item } => {
+                write!(f, "{}: {item} can't be saved", path.display())
+            }
+            SceneFileError::Invalid { path, message } => {
+                write!(f, "{}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneFileError::Io { source, .. } => Some(source),
+            SceneFileError::Parse { source, .. } => Some(source),
+            SceneFileError::Serialize { source, .. } => Some(source),
+            SceneFileError::UnsupportedVersion { .. }
+            | SceneFileError::NotDescribable { .. }
+            | SceneFileError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl Scene {
+    /// Load a scene and the settings to render it with from a scene file
+    /// The aspect ratio of the camera follows the resolution of the render settings
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Scene, RenderSettings), SceneFileError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|source| SceneFileError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let parse_error = |source| SceneFileError::Parse {
+            path: path.to_owned(),
+            source,
+        };
+
+        let VersionDescription { version } = ron::from_str(&source).map_err(parse_error)?;
+        if version > SCENE_FORMAT_VERSION {
+            return Err(SceneFileError::UnsupportedVersion {
+                path: path.to_owned(),
+                version,
+            });
+        }
+
+        let mut description: SceneDescription = ron::from_str(&source).map_err(parse_error)?;
+        if let Some(directory) = path.parent() {
+            for file_path in description.file_paths_mut() {
+                *file_path = directory.join(&*file_path);
+            }
+        }
+
+        description
+            .build()
+            .map_err(|message| SceneFileError::Invalid {
+                path: path.to_owned(),
+                message,
+            })
+    }
+
+    /// Save the scene and the settings to render it with to a scene file
+    /// Fails if any part of the scene has no description, for example a material implemented outside of this library
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        path: P,
+        render_settings: &RenderSettings,
+    ) -> Result<(), SceneFileError> {
+        let path = path.as_ref();
+        let mut description = SceneDescription::new(self, render_settings).map_err(|item| {
+            SceneFileError::NotDescribable {
+                path: path.to_owned(),
+                item,
+            }
+        })?;
+
+        // Files referenced by the scene are stored relative to the scene file, so the scene can be moved with them
+        if let Some(directory) = path.parent() {
+            for file_path in description.file_paths_mut() {
+                *file_path = relative_path(file_path, directory);
+            }
+        }
+
+        let config = ron::ser::PrettyConfig::new().struct_names(false);
+        let source = ron::ser::to_string_pretty(&description, config).map_err(|source| {
+            SceneFileError::Serialize {
+                path: path.to_owned(),
+                source,
+            }
+        })?;
+
+        fs::write(path, source).map_err(|source| SceneFileError::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+/// Path leading from the directory to the same file, falls back to the absolute path if there is none
+/// such as for a file on another drive
+fn relative_path(path: &Path, directory: &Path) -> PathBuf {
+    // The parent of a file name without a directory is empty
+    let directory = match directory.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => directory,
+    };
+    let (Some(path), Some(directory)) = (normalized_path(path), normalized_path(directory)) else {
+        return path.to_owned();
+    };
+
+    let mut path_components = path.components().peekable();
+    let mut directory_components = directory.components().peekable();
+    let mut is_shared = false;
+    while let (Some(a), Some(b)) = (path_components.peek(), directory_components.peek()) {
+        if a != b {
+            break;
+        }
+
+        is_shared = true;
+        path_components.next();
+        directory_components.next();
+    }
+
+    if !is_shared {
+        return path;
+    }
+
+    directory_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect()
+}
+
+/// Absolute form of the path with every ```..``` removing the component before it
+fn normalized_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in std::path::absolute(path).ok()?.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+
+    Some(normalized)
+}
+
+impl SceneDescription {
+    /// Describe the scene and render settings
+    /// Returns the name of the first part without a description on failure
+    pub fn new(scene: &Scene, render_settings: &RenderSettings) -> Result<Self, String> {
+        let camera = &scene.camera;
+        let mut materials: Vec<&Arc<dyn Material>> = Vec::new();
+        let mut objects = Vec::new();
+
+        for (index, object) in scene.objects().iter().enumerate() {
+            let shape = object
+                .shape
+                .describe()
+                .ok_or_else(|| format!("the shape of object {index}"))?;
+
+            // Objects sharing a material keep sharing it after loading
+            let material = match materials.iter().position(|material| {
+                ptr::addr_eq(Arc::as_ptr(material), Arc::as_ptr(&object.material))
+            }) {
+                Some(material) => material,
+                None => {
+                    materials.push(&object.material);
+                    materials.len() - 1
+                }
+            };
+
+            objects.push(ObjectDescription { shape, material });
+        }
+
+        let materials = materials
+            .into_iter()
+            .map(|material| material.describe().ok_or_else(|| "a material".to_owned()))
+            .collect::<Result<_, _>>()?;
+
+        let lights = scene
+            .lights()
+            .enumerate()
+            .map(|(index, light)| light.describe().ok_or_else(|| format!("light {index}")))
+            .collect::<Result<_, _>>()?;
+
+        let background = scene
+            .background
+            .describe()
+            .ok_or_else(|| "the background".to_owned())?;
+
+        Ok(Self {
+            version: SCENE_FORMAT_VERSION,
+            camera: CameraDescription {
+                origin: camera.origin,
+                direction: camera.direction(),
+                up: camera.up(),
+                vertical_fov_degrees: camera.vertical_fov().to_degrees(),
+                aperture_radius: camera.aperture_radius(),
+                focus_distance: camera.focus_distance(),
+            },
+            materials,
+            objects,
+            lights,
+            background,
+            render_settings: RenderSettingsDescription::new(render_settings)?,
+        })
+    }
+
+    /// Paths of the files the scene refers to, such as textures and the image of an environment map
+    fn file_paths_mut(&mut self) -> Vec<&mut PathBuf> {
+        let mut paths = Vec::new();
+        for material in &mut self.materials {
+            if let MaterialDescription::Pbr {
+                base_color_texture,
+                metallic_roughness_texture,
+                ..
+            } = material
+            {
+                for texture in [base_color_texture, metallic_roughness_texture] {
+                    if let Some(TextureDescription::File { path, .. }) = texture {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+
+        if let BackgroundDescription::Environment { path, .. } = &mut self.background {
+            paths.push(path);
+        }
+
+        paths
+    }
+
+    /// Create the described scene and render settings
+    /// Returns a message describing the first invalid value on failure
+    pub fn build(self) -> Result<(Scene, RenderSettings), String> {
+        let render_settings = self.render_settings.build()?;
+        let resolution = render_settings.resolution;
+
+        let camera = self.camera;
+        if camera.direction.length_squared() == 0.0 || camera.up.length_squared() == 0.0 {
+            return Err("the camera direction and up vector can't be zero".to_owned());
+        }
+
+        if camera.aperture_radius < 0.0 || camera.focus_distance <= 0.0 {
+            return Err(
+                "the camera aperture radius can't be negative and the focus distance has to be positive"
+                    .to_owned(),
+            );
+        }
+
+        let mut scene = Scene::new(PerspectiveCamera::new(
+            camera.origin,
+            camera.direction.normalize(),
+            camera.up,
+            camera.vertical_fov_degrees.to_radians(),
+            resolution.width as f32 / resolution.height as f32,
+        ));
+        scene
+            .camera
+            .set_lens(camera.aperture_radius, camera.focus_distance);
+
+        let materials = self
+            .materials
+            .into_iter()
+            .enumerate()
+            .map(|(index, material)| {
+                material
+                    .build()
+                    .map_err(|message| format!("material {index}: {message}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (index, object) in self.objects.into_iter().enumerate() {
+            let material = materials.get(object.material).ok_or_else(|| {
+                format!(
+                    "object {index}: material index {} out of range",
+                    object.material
+                )
+            })?;
+            let shape = object
+                .shape
+                .build()
+                .map_err(|message| format!("object {index}: {message}"))?;
+
+            scene.add_object(Object {
+                shape,
+                material: Arc::clone(material),
+            });
+        }
+
+        for (index, light) in self.lights.into_iter().enumerate() {
+            light
+                .add_to(&mut scene)
+                .map_err(|message| format!("light {index}: {message}"))?;
+        }
+
+        scene.background = self
+            .background
+            .build()
+            .map_err(|message| format!("background: {message}"))?;
+
+        Ok((scene, render_settings))
+    }
+}
+
+impl ShapeDescription {
+    fn build(self) -> Result<Box<dyn Shape>, String> {
+        Ok(match self {
+            ShapeDescription::Sphere { center, radius } => {
+                if radius <= 0.0 {
+                    return Err("sphere radius has to be positive".to_owned());
+                }
+
+                Box::new(Sphere::new(center, radius))
+            }
+            ShapeDescription::Triangle {
+                vertices: [a, b, c],
+            } => Box::new(Triangle::new(a, b, c)),
+            ShapeDescription::Rectangle {
+                corner,
+                edge_u,
+                edge_v,
+            } => {
+                if edge_u.cross(edge_v).length_squared() == 0.0 {
+                    return Err("rectangle edges can't be zero or parallel".to_owned());
+                }
+
+                Box::new(Rectangle::new(corner, edge_u, edge_v))
+            }
+            ShapeDescription::Disk {
+                center,
+                normal,
+                radius,
+            } => {
+                if radius <= 0.0 || normal.length_squared() == 0.0 {
+                    return Err("disk needs a positive radius and a nonzero normal".to_owned());
+                }
+
+                Box::new(Disk::new(center, normal, radius))
+            }
+            ShapeDescription::Mesh {
+                positions,
+                indices,
+                normals,
+                uvs,
+            } => {
+                if let Some(index) = indices
+                    .iter()
+                    .flatten()
+                    .find(|&&index| index as usize >= positions.len())
+                {
+                    return Err(format!("mesh vertex index {index} out of range"));
+                }
+
+                for (name, count) in [("normals", normals.len()), ("uvs", uvs.len())] {
+                    if count != 0 && count != positions.len() {
+                        return Err(format!(
+                            "mesh has {count} {name} but {} positions",
+                            positions.len()
+                        ));
+                    }
+                }
+
+                let mut mesh = TriangleMesh::new(positions, indices);
+                if !normals.is_empty() {
+                    mesh = mesh.with_normals(normals);
+                }
+                if !uvs.is_empty() {
+                    mesh = mesh.with_uvs(uvs);
+                }
+
+                Box::new(mesh)
+            }
+        })
+    }
+}
+
+impl MaterialDescription {
+    fn build(self) -> Result<Arc<dyn Material>, String> {
+        Ok(match self {
+            MaterialDescription::Lambertian { albedo } => Arc::new(Lambertian::new(albedo)),
+            MaterialDescription::Phong {
+                diffuse,
+                specular,
+                exponent,
+            } => {
+                if exponent < 0.0 {
+                    return Err("Phong exponent can't be negative".to_owned());
+                }
+
+                Arc::new(Phong::new(diffuse, specular, exponent))
+            }
+            MaterialDescription::Metal { albedo, roughness } => {
+                Arc::new(Metal::new(albedo, roughness))
+            }
+            MaterialDescription::Dielectric { ior, tint } => {
+                if ior <= 0.0 {
+                    return Err("index of refraction has to be positive".to_owned());
+                }
+
+                Arc::new(Dielectric::with_tint(ior, tint))
+            }
+            MaterialDescription::Emissive { color, strength } => {
+                Arc::new(Emissive::new(color, strength))
+            }
+            MaterialDescription::Pbr {
+                base_color,
+                metallic,
+                roughness,
+                emission,
+                base_color_texture,
+                metallic_roughness_texture,
+            } => {
+                let mut material =
+                    PbrMaterial::new(base_color, metallic, roughness).with_emission(emission);
+
+                if let Some(texture) = base_color_texture {
+                    material = material.with_base_color_texture(texture.build()?);
+                }
+
+                if let Some(texture) = metallic_roughness_texture {
+                    material = material.with_metallic_roughness_texture(texture.build()?);
+                }
+
+                Arc::new(material)
+            }
+        })
+    }
+}
+
+impl TextureDescription {
+    pub fn new(texture: &Texture) -> Self {
+        match texture.source() {
+            Some((path, srgb)) => TextureDescription::File {
+                path: path.to_owned(),
+                srgb,
+            },
+            None => TextureDescription::Texels {
+                width: texture.width(),
+                height: texture.height(),
+                texels: texture.texels().to_vec(),
+            },
+        }
+    }
+
+    fn build(self) -> Result<Arc<Texture>, String> {
+        let texture = match self {
+            TextureDescription::File { path, srgb } => {
+                Texture::load(path, srgb).map_err(|err| err.to_string())?
+            }
+            TextureDescription::Texels {
+                width,
+                height,
+                texels,
+            } => {
+                if width == 0 || height == 0 || texels.len() != width as usize * height as usize {
+                    return Err(format!(
+                        "texture of size {width}x{height} has {} texels",
+                        texels.len()
+                    ));
+                }
+
+                Texture::new(width, height, texels)
+            }
+        };
+
+        Ok(Arc::new(texture))
+    }
+}
+
+impl LightDescription {
+    /// Add the described light to a scene that already contains all objects
+    fn add_to(self, scene: &mut Scene) -> Result<(), String> {
+        let light: Box<dyn Light> = match self {
+            LightDescription::Ambient { color } => Box::new(AmbientLight::new(color)),
+            LightDescription::Directional { color, direction } => {
+                if direction.length_squared() == 0.0 {
+                    return Err("the direction of a directional light can't be zero".to_owned());
+                }
+
+                Box::new(DirectionalLight::new(color, direction))
+            }
+            LightDescription::Point {
+                intensity,
+                position,
+            } => Box::new(PointLight::new(intensity, position)),
+            LightDescription::Spot {
+                intensity,
+                position,
+                direction,
+                inner_angle_degrees,
+                outer_angle_degrees,
+            } => {
+                if direction.length_squared() == 0.0 {
+                    return Err("the direction of a spot light can't be zero".to_owned());
+                }
+
+                if !(0.0 <= inner_angle_degrees
+                    && inner_angle_degrees <= outer_angle_degrees
+                    && outer_angle_degrees <= 180.0)
+                {
+                    return Err(
+                        "spot light angles need 0 <= inner <= outer <= 180 degrees".to_owned()
+                    );
+                }
+
+                Box::new(SpotLight::new(
+                    intensity,
+                    position,
+                    direction,
+                    inner_angle_degrees.to_radians(),
+                    outer_angle_degrees.to_radians(),
+                ))
+            }
+            LightDescription::Area {
+                object,
+                sample_count,
+            } => {
+                let shape = &scene
+                    .objects()
+                    .get(object)
+                    .ok_or_else(|| format!("area light object index {object} out of range"))?
+                    .shape;
+                if shape.area() <= 0.0 {
+                    return Err(format!(
+                        "the shape of object {object} can't be an area light"
+                    ));
+                }
+
+                scene.set_area_light(object, sample_count);
+                return Ok(());
+            }
+        };
+
+        scene.add_boxed_light(light);
+        Ok(())
+    }
+}
+
+impl BackgroundDescription {
+    fn build(self) -> Result<Background, String> {
+        Ok(match self {
+            BackgroundDescription::Color { color } => Background::Color(color),
+            BackgroundDescription::Gradient { bottom, top } => Background::Gradient { bottom, top },
+            BackgroundDescription::Environment {
+                path,
+                rotation_degrees,
+                intensity,
+            } => {
+                if intensity < 0.0 {
+                    return Err("the environment intensity can't be negative".to_owned());
+                }
+
+                let map = EnvironmentMap::load(path).map_err(|err| err.to_string())?;
+                Background::Environment(
+                    map.with_rotation(rotation_degrees.to_radians())
+                        .with_intensity(intensity),
+                )
+            }
+            BackgroundDescription::Sky {
+                sun,
+                turbidity,
+                intensity,
+            } => {
+                if !(1.7..=10.0).contains(&turbidity) || intensity < 0.0 {
+                    return Err(
+                        "the sky turbidity has to be between 1.7 and 10 and its intensity can't be negative"
+                            .to_owned(),
+                    );
+                }
+
+                Background::Sky(PreethamSky::new(sun.build()?, turbidity).with_intensity(intensity))
+            }
+        })
+    }
+}
+
+impl SunDescription {
+    fn build(self) -> Result<Vec3, String> {
+        match self {
+            SunDescription::Direction(direction) => {
+                if direction.length_squared() == 0.0 {
+                    return Err("the sun direction can't be zero".to_owned());
+                }
+
+                Ok(direction)
+            }
+            SunDescription::Time {
+                day_of_year,
+                solar_hour,
+                latitude_degrees,
+            } => {
+                if !(1..=366).contains(&day_of_year)
+                    || !(0.0..=24.0).contains(&solar_hour)
+                    || !(-90.0..=90.0).contains(&latitude_degrees)
+                {
+                    return Err("the sun needs a day of 1 to 366, an hour of 0 to 24 and a latitude of -90 to 90 degrees".to_owned());
+                }
+
+                Ok(sky::sun_direction(
+                    day_of_year,
+                    solar_hour,
+                    latitude_degrees.to_radians(),
+                ))
+            }
+        }
+    }
+}
+
+impl RenderSettingsDescription {
+    pub fn new(settings: &RenderSettings) -> Result<Self, String> {
+        Ok(Self {
+            resolution: settings.resolution,
+            number_of_samples: settings.number_of_samples,
+            sampler: settings
+                .sampler
+                .describe()
+                .ok_or_else(|| "the sampler".to_owned())?,
+            render_mode: match settings.render_mode {
+                RenderMode::Full => RenderModeDescription::Full,
+                RenderMode::Progressive {
+                    max_samples,
+                    time_budget,
+                } => RenderModeDescription::Progressive {
+                    max_samples,
+                    time_budget_seconds: time_budget.map(|budget| budget.as_secs_f32()),
+                },
+            },
+            max_depth: settings.max_depth,
+            integrator: settings
+                .integrator
+                .describe()
+                .ok_or_else(|| "the integrator".to_owned())?,
+            aovs: settings.aovs.clone(),
+        })
+    }
+
+    fn build(self) -> Result<RenderSettings, String> {
+        if self.resolution.width < 2 || self.resolution.height < 2 {
+            return Err("resolution has to be at least 2x2".to_owned());
+        }
+
+        if self.number_of_samples == 0 {
+            return Err("number of samples has to be at least 1".to_owned());
+        }
+
+        let check_samples_per_side = |name: &str, samples_per_side: u32| {
+            if samples_per_side == 0 {
+                Err(format!("{name} sampler needs at least one sample per side"))
+            } else {
+                Ok(samples_per_side)
+            }
+        };
+
+        let sampler: Arc<dyn SampleGenerator> = match self.sampler {
+            SamplerDescription::Regular { samples_per_side } => Arc::new(RegularSampler::new(
+                check_samples_per_side("regular", samples_per_side)?,
+            )),
+            SamplerDescription::Random { seed } => Arc::new(RandomSampler::new(seed)),
+            SamplerDescription::Jittered {
+                samples_per_side,
+                seed,
+            } => Arc::new(JitteredSampler::new(
+                check_samples_per_side("jittered", samples_per_side)?,
+                seed,
+            )),
+            SamplerDescription::MultiJittered {
+                samples_per_side,
+                seed,
+            } => Arc::new(MultiJitteredSampler::new(
+                check_samples_per_side("multi-jittered", samples_per_side)?,
+                seed,
+            )),
+            SamplerDescription::Halton { seed } => Arc::new(HaltonSampler::new(seed)),
+            SamplerDescription::Sobol { seed } => Arc::new(SobolSampler::new(seed)),
+        };
+
+        let render_mode = match self.render_mode {
+            RenderModeDescription::Full => RenderMode::Full,
+            RenderModeDescription::Progressive {
+                max_samples,
+                time_budget_seconds,
+            } => RenderMode::Progressive {
+                max_samples,
+                time_budget: time_budget_seconds
+                    .map(Duration::try_from_secs_f32)
+                    .transpose()
+                    .map_err(|_| "invalid time budget".to_owned())?,
+            },
+        };
+
+        let integrator: Arc<dyn Integrator> = match self.integrator {
+            IntegratorDescription::PathTracer => Arc::new(PathTracer),
+            IntegratorDescription::Whitted => Arc::new(WhittedIntegrator),
+            IntegratorDescription::AmbientOcclusion { samples, distance } => {
+                if samples == 0 {
+                    return Err("ambient occlusion needs at least one sample".to_owned());
+                }
+
+                Arc::new(AmbientOcclusionIntegrator::new(samples, distance))
+            }
+            IntegratorDescription::Debug { mode } => Arc::new(DebugIntegrator::new(mode)),
+        };
+
+        Ok(RenderSettings {
+            resolution: self.resolution,
+            number_of_samples: self.number_of_samples,
+            sampler,
+            render_mode,
+            max_depth: self.max_depth,
+            integrator,
+            aovs: self.aovs,
+        })
+    }
+}
+
+impl Default for RenderSettingsDescription {
+    fn default() -> Self {
+        Self::new(&RenderSettings::default())
+            .expect("The default render settings can be described.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::Arc};
+
+    use glam::Vec3;
+
+    use crate::{
+        color::{self, Color},
+        image::{write_hdr, write_png},
+        scene::{
+            background::Background, material::PbrMaterial, object::Object, sphere::Sphere,
+            texture::Texture, Scene,
+        },
+        test_util::temp_path,
+        tracer::RenderSettings,
+    };
+
+    use super::{MaterialDescription, SceneFileError, TextureDescription, SCENE_FORMAT_VERSION};
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round_trip.ron");
+        let scene = Scene::default();
+        let settings = RenderSettings {
+            max_depth: 7,
+            ..Default::default()
+        };
+
+        scene.save(&path, &settings).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let (loaded, loaded_settings) = Scene::load(&path).unwrap();
+        loaded.save(&path, &loaded_settings).unwrap();
+        let resaved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.objects().len(), scene.objects().len());
+        assert_eq!(loaded.lights().count(), scene.lights().count());
+        assert_eq!(loaded_settings.max_depth, 7);
+        assert_eq!(saved, resaved);
+    }
+
+    #[test]
+    fn shipped_scene_loads() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../scenes/spheres.ron");
+        let (scene, _) = Scene::load(path).unwrap();
+
+        assert_eq!(scene.objects().len(), 2);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let path = temp_path("newer_version.ron");
+        let source = format!(
+            "(version: {}, camera: (), future_field: 1)",
+            SCENE_FORMAT_VERSION + 1
+        );
+        fs::write(&path, source).unwrap();
+        let result = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(SceneFileError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_material_index_is_rejected() {
+        let path = temp_path("invalid_material.ron");
+        let source = "(
+            version: 1,
+            camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+            objects: [(shape: Sphere(center: (0, 0, 5), radius: 1), material: 0)],
+        )";
+        fs::write(&path, source).unwrap();
+        let result = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(SceneFileError::Invalid { .. })));
+    }
+
+    #[test]
+    fn zero_samples_is_rejected() {
+        let path = temp_path("zero_samples.ron");
+        let source = "(
+            version: 1,
+            camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+            render_settings: (number_of_samples: 0),
+        )";
+        fs::write(&path, source).unwrap();
+        let result = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(SceneFileError::Invalid { .. })));
+    }
+
+    #[test]
+    fn area_light_round_trip() {
+        let path = temp_path("area_light.ron");
+        let source = "(
+            version: 1,
+            camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+            materials: [Emissive(color: (1, 1, 1), strength: 5)],
+            objects: [
+                (shape: Rectangle(corner: (0, 2, 0), edge_u: (1, 0, 0), edge_v: (0, 0, 1)), material: 0),
+                (shape: Disk(center: (0, 3, 0), normal: (0, -1, 0), radius: 0.5), material: 0),
+            ],
+            lights: [Area(object: 1, sample_count: 8)],
+        )";
+        fs::write(&path, source).unwrap();
+        let (scene, settings) = Scene::load(&path).unwrap();
+        scene.save(&path, &settings).unwrap();
+        let (loaded, _) = Scene::load(&path).unwrap();
+
+        fs::write(&path, source.replace("object: 1", "object: 2")).unwrap();
+        let result = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(!loaded.is_area_light(0));
+        assert!(loaded.is_area_light(1));
+        assert_eq!(loaded.lights().next().unwrap().sample_count(), 8);
+        assert!(matches!(result, Err(SceneFileError::Invalid { .. })));
+    }
+
+    #[test]
+    fn environment_background_round_trip() {
+        let image_path = temp_path("environment.hdr");
+        let colors = vec![Color::new(1.0, 2.0, 4.0); 8];
+        write_hdr(&image_path, 4, 2, &colors).unwrap();
+
+        // The image is found next to the scene file
+        let path = temp_path("environment.ron");
+        let source = format!(
+            "(
+                version: 1,
+                camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+                background: Environment(path: \"{}\", rotation_degrees: 90, intensity: 0.5),
+            )",
+            image_path.file_name().unwrap().to_str().unwrap()
+        );
+        fs::write(&path, source).unwrap();
+        let (scene, settings) = Scene::load(&path).unwrap();
+        scene.save(&path, &settings).unwrap();
+        let (loaded, _) = Scene::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&image_path).unwrap();
+
+        let Background::Environment(map) = &loaded.background else {
+            panic!("expected an environment map");
+        };
+        assert!((map.rotation().to_degrees() - 90.0).abs() < 1e-3);
+        assert_eq!(map.intensity(), 0.5);
+        assert!((loaded.background_color(&Vec3::Y).b - 2.0).abs() < 0.02);
+        assert_eq!(loaded.sampled_lights().count(), 1);
+    }
+
+    #[test]
+    fn relative_environment_path_round_trip() {
+        let directory = temp_path("scenes");
+        let nested = directory.join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        let colors = vec![Color::new(1.0, 2.0, 4.0); 8];
+        write_hdr(&directory.join("environment.hdr"), 4, 2, &colors).unwrap();
+        let source = "(
+            version: 1,
+            camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+            background: Environment(path: \"../environment.hdr\"),
+        )";
+        fs::write(nested.join("scene.ron"), source).unwrap();
+
+        // Saving next to the image and back into the nested directory rewrites the path relative to each file
+        let round_trip = || -> Result<(String, String), SceneFileError> {
+            let (scene, settings) = Scene::load(nested.join("scene.ron"))?;
+            scene.save(directory.join("scene.ron"), &settings)?;
+            let (scene, settings) = Scene::load(directory.join("scene.ron"))?;
+            scene.save(nested.join("scene.ron"), &settings)?;
+            Scene::load(nested.join("scene.ron"))?;
+
+            let read = |path: PathBuf| fs::read_to_string(path).unwrap_or_default();
+            Ok((
+                read(directory.join("scene.ron")),
+                read(nested.join("scene.ron")),
+            ))
+        };
+        let result = round_trip();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let (saved, nested_saved) = result.unwrap();
+        assert!(saved.contains("\"environment.hdr\""));
+        let parent = PathBuf::from("..").join("environment.hdr");
+        assert!(nested_saved.contains(&format!("{:?}", parent.to_str().unwrap())));
+    }
+
+    #[test]
+    fn textures_are_stored_by_path() {
+        let image_path = temp_path("texture.png");
+        write_png(&image_path, 1, 1, &[[255, 128, 0, 255]]).unwrap();
+
+        let path = temp_path("texture.ron");
+        let source = format!(
+            "(
+                version: 1,
+                camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+                materials: [
+                    Pbr(
+                        base_color: (1, 1, 1),
+                        metallic: 0,
+                        roughness: 1,
+                        base_color_texture: Some(File(path: \"{}\", srgb: true)),
+                    ),
+                ],
+                objects: [(shape: Sphere(center: (0, 0, 2), radius: 1), material: 0)],
+            )",
+            image_path.file_name().unwrap().to_str().unwrap()
+        );
+        fs::write(&path, source).unwrap();
+        let (scene, settings) = Scene::load(&path).unwrap();
+        scene.save(&path, &settings).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let reloaded = Scene::load(&path).map(|_| ());
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&image_path).unwrap();
+
+        // Only the file is stored, not its texels
+        let file_name = image_path.file_name().unwrap().to_str().unwrap();
+        assert!(saved.contains(&format!("path: \"{file_name}\"")));
+        assert!(saved.contains("srgb: true"));
+        reloaded.unwrap();
+    }
+
+    #[test]
+    fn textures_without_a_file_store_their_texels() {
+        let texels = vec![Color::new(0.25, 0.5, 1.0), Color::new(2.0, 0.0, 0.0)];
+        let material = PbrMaterial::new(color::WHITE, 0.0, 1.0)
+            .with_base_color_texture(Arc::new(Texture::new(2, 1, texels.clone())));
+        let mut scene = Scene::default();
+        scene.add_object(Object::new(Sphere::new(Vec3::Z * 3.0, 1.0), material));
+
+        let path = temp_path("texels.ron");
+        scene.save(&path, &RenderSettings::default()).unwrap();
+        let loaded = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let (loaded, _) = loaded.unwrap();
+        let object = loaded.objects().last().unwrap();
+        let Some(MaterialDescription::Pbr {
+            base_color_texture: Some(TextureDescription::Texels { texels: loaded, .. }),
+            ..
+        }) = object.material.describe()
+        else {
+            panic!("expected a texture with texels");
+        };
+        assert_eq!(loaded, texels);
+    }
+
+    #[test]
+    fn sky_background_round_trip() {
+        let path = temp_path("sky.ron");
+        let source = "(
+            version: 1,
+            camera: (origin: (0, 0, 0), direction: (0, 0, 1), up: (0, 1, 0), vertical_fov_degrees: 60),
+            background: Sky(sun: Time(day_of_year: 172, solar_hour: 15, latitude_degrees: 45), turbidity: 4),
+        )";
+        fs::write(&path, source).unwrap();
+        let (scene, settings) = Scene::load(&path).unwrap();
+        scene.save(&path, &settings).unwrap();
+        let (loaded, _) = Scene::load(&path).unwrap();
+
+        fs::write(&path, source.replace("turbidity: 4", "turbidity: 20")).unwrap();
+        let result = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let (Background::Sky(sky), Background::Sky(loaded_sky)) =
+            (&scene.background, &loaded.background)
+        else {
+            panic!("expected a sky");
+        };
+        assert!(sky.sun_direction().y > 0.5);
+        assert!(loaded_sky.sun_direction().distance(sky.sun_direction()) < 1e-5);
+        assert_eq!(loaded_sky.turbidity(), 4.0);
+        assert!(matches!(result, Err(SceneFileError::Invalid { .. })));
+    }
+}
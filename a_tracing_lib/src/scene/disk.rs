@@ -0,0 +1,135 @@
+use std::f32::consts::{PI, TAU};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    ray::{HitRecord, Ray},
+    sampling::concentric_sample_disk,
+};
+
+use super::{aabb::Aabb, description::ShapeDescription, object::Shape};
+
+/// Flat circular disk facing along its normal
+pub struct Disk {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32) -> Self {
+        Self {
+            center,
+            normal: normal.normalize(),
+            radius,
+        }
+    }
+
+    fn surface_record(&self, point: Vec3, t: f32) -> HitRecord {
+        // Polar coordinates of the point, u around the normal and v outwards from the center
+        let (tangent, bitangent) = self.normal.any_orthonormal_pair();
+        let offset = point - self.center;
+        let u = 0.5 + offset.dot(bitangent).atan2(offset.dot(tangent)) / TAU;
+        let v = offset.length() / self.radius;
+
+        HitRecord {
+            point,
+            normal: self.normal,
+            t,
+            uv: Vec2::new(u, v),
+            barycentric: Vec2::ZERO,
+        }
+    }
+}
+
+impl Shape for Disk {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction);
+
+        // The ray is parallel to the disk
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        if point.distance_squared(self.center) > self.radius * self.radius {
+            return None;
+        }
+
+        Some(self.surface_record(point, t))
+    }
+
+    fn bounds(&self) -> Aabb {
+        // The extent along every axis shrinks the more the normal points along it
+        let squared = (Vec3::ONE - self.normal * self.normal).max(Vec3::ZERO);
+        let extent = self.radius * Vec3::new(squared.x.sqrt(), squared.y.sqrt(), squared.z.sqrt());
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, sample: Vec2) -> Option<HitRecord> {
+        let (tangent, bitangent) = self.normal.any_orthonormal_pair();
+        let offset = self.radius * concentric_sample_disk(sample);
+        let point = self.center + offset.x * tangent + offset.y * bitangent;
+        Some(self.surface_record(point, 0.0))
+    }
+
+    fn describe(&self) -> Option<ShapeDescription> {
+        Some(ShapeDescription::Disk {
+            center: self.center,
+            normal: self.normal,
+            radius: self.radius,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use crate::{ray::Ray, scene::object::Shape};
+
+    use super::Disk;
+
+    #[test]
+    fn hit_test() {
+        let disk = Disk::new(Vec3::new(0.0, 0.0, -2.0), Vec3::Z, 1.0);
+        let ray = |x: f32| Ray {
+            origin: Vec3::new(x, 0.0, 0.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        let record = disk.hit(&ray(0.5), 0.0, f32::MAX).unwrap();
+        assert!((record.t - 2.0).abs() < 1e-6);
+        assert_eq!(record.normal, Vec3::Z);
+        assert!(disk.hit(&ray(1.5), 0.0, f32::MAX).is_none());
+    }
+
+    #[test]
+    fn samples_lie_on_disk() {
+        let disk = Disk::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 1.0, 0.0), 2.0);
+        let bounds = disk.bounds();
+
+        for sample in [
+            Vec2::new(0.1, 0.9),
+            Vec2::new(0.99, 0.5),
+            Vec2::new(0.5, 0.0),
+        ] {
+            let record = disk.sample_surface(sample).unwrap();
+            let offset = record.point - disk.center;
+
+            assert!(offset.dot(disk.normal).abs() < 1e-5);
+            assert!(offset.length() <= 2.0 + 1e-5);
+            assert!((bounds.min.cmple(record.point + 1e-5)).all());
+            assert!((bounds.max.cmpge(record.point - 1e-5)).all());
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use crate::color::Color;
+use crate::{color::Color, sampling::SampleStream, scene::description::LightDescription};
 
-use super::Light;
+use super::{Light, LightSample};
 
 pub struct AmbientLight {
     color: Color,
@@ -13,14 +13,22 @@ impl AmbientLight {
 }
 
 impl Light for AmbientLight {
-    fn light_at(
+    fn sample(
         &self,
         _scene: &crate::scene::Scene,
         _location: glam::Vec3,
-    ) -> Option<super::LightRay> {
-        Some(super::LightRay {
+        _sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        Some(LightSample {
             direction: None,
-            color: self.color,
+            radiance: self.color,
+            pdf: 1.0,
+            distance: 0.0,
+            is_delta: true,
         })
     }
+
+    fn describe(&self) -> Option<LightDescription> {
+        Some(LightDescription::Ambient { color: self.color })
+    }
 }
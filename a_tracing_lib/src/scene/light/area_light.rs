@@ -0,0 +1,220 @@
+use glam::Vec3;
+
+use crate::{
+    ray::Ray,
+    sampling::SampleStream,
+    scene::{description::LightDescription, Scene, HIT_EPSILON},
+};
+
+use super::{Light, LightSample};
+
+/// Emissive object of the scene whose surface is sampled directly, giving soft shadows
+/// The emitted light comes from the material of the object, its shape has to support ```sample_surface```
+/// Area lights are created through ```Scene::add_area_light``` which keeps the object index up to date
+#[derive(Debug, Clone, Copy)]
+pub struct AreaLight {
+    object_index: usize,
+    sample_count: u32,
+}
+
+impl AreaLight {
+    pub(crate) fn new(object_index: usize, sample_count: u32) -> Self {
+        Self {
+            object_index,
+            sample_count: sample_count.max(1),
+        }
+    }
+
+    /// Index of the emitting object in the objects of the scene
+    pub fn object_index(&self) -> usize {
+        self.object_index
+    }
+
+    pub(crate) fn set_object_index(&mut self, object_index: usize) {
+        self.object_index = object_index;
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(
+        &self,
+        scene: &Scene,
+        location: Vec3,
+        sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        let object = scene.objects().get(self.object_index)?;
+        let area = object.shape.area();
+        let surface = object.shape.sample_surface(sampler.next_vec2())?;
+
+        let to_light = surface.point - location;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+
+        let direction = to_light / distance;
+        let cos_light = surface.normal.dot(direction).abs();
+        let radiance = object.material.emitted(-direction, &surface);
+        if cos_light == 0.0 || radiance.max_component() <= 0.0 {
+            return None;
+        }
+
+        // Convert the uniform area pdf 1 / area to solid angle at the location
+        Some(LightSample {
+            direction: Some(direction),
+            radiance,
+            pdf: distance * distance / (cos_light * area),
+            distance,
+            is_delta: false,
+        })
+    }
+
+    fn pdf(&self, scene: &Scene, location: Vec3, direction: Vec3) -> f32 {
+        let Some(object) = scene.objects().get(self.object_index) else {
+            return 0.0;
+        };
+
+        let direction = direction.normalize();
+        let ray = Ray {
+            origin: location,
+            direction,
+        };
+        let Some(surface) = object.shape.hit(&ray, HIT_EPSILON, f32::MAX) else {
+            return 0.0;
+        };
+
+        let cos_light = surface.normal.dot(direction).abs();
+        let area = object.shape.area();
+        if cos_light == 0.0 || area <= 0.0 {
+            return 0.0;
+        }
+
+        surface.t * surface.t / (cos_light * area)
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    fn describe(&self) -> Option<LightDescription> {
+        Some(LightDescription::Area {
+            object: self.object_index,
+            sample_count: self.sample_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::{
+        color::{self, Color},
+        sampling::{RandomSampler, SampleStream},
+        scene::{
+            camera::PerspectiveCamera,
+            material::{Emissive, Lambertian},
+            object::Object,
+            rectangle::Rectangle,
+            sphere::Sphere,
+            Scene,
+        },
+    };
+
+    /// Scene with a small downward facing emitter of area 0.01 two units above the origin
+    fn lit_scene() -> Scene {
+        let mut scene = Scene::new(PerspectiveCamera::default());
+        let emitter = Rectangle::new(
+            Vec3::new(-0.05, 2.0, -0.05),
+            Vec3::new(0.1, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.1),
+        );
+        scene.add_area_light(Object::new(emitter, Emissive::new(color::WHITE, 4.0)), 4);
+        scene
+    }
+
+    fn average_light(scene: &Scene, location: Vec3) -> Color {
+        let light = scene.lights().next().unwrap();
+        let generator = RandomSampler::new(0);
+        let mut sum = color::BLACK;
+
+        for sample_number in 0..light.sample_count() {
+            let mut sampler = SampleStream::new(&generator, 0, sample_number);
+            if let Some(light_ray) = light.light_at(scene, location, &mut sampler) {
+                sum += light_ray.color;
+            }
+        }
+
+        sum / light.sample_count() as f32
+    }
+
+    #[test]
+    fn small_emitter_behaves_like_point() {
+        let scene = lit_scene();
+        assert_eq!(scene.lights().next().unwrap().sample_count(), 4);
+
+        // Radiance 4 times area 0.01 over distance squared 4
+        let color = average_light(&scene, Vec3::ZERO);
+        assert!((color.g - 0.01).abs() < 1e-3);
+
+        // The back of the emitter is dark
+        assert_eq!(
+            average_light(&scene, Vec3::new(0.0, 4.0, 0.0)),
+            color::BLACK
+        );
+    }
+
+    #[test]
+    fn occluded_by_objects() {
+        let mut scene = lit_scene();
+        scene.add_object(Object::new(
+            Sphere::new(Vec3::new(0.0, 1.0, 0.0), 0.5),
+            Lambertian::new(color::WHITE),
+        ));
+        assert_eq!(average_light(&scene, Vec3::ZERO), color::BLACK);
+
+        scene.remove_object(1);
+        assert!(average_light(&scene, Vec3::ZERO).g > 0.0);
+    }
+
+    #[test]
+    fn removing_objects_keeps_index() {
+        let mut scene = Scene::new(PerspectiveCamera::default());
+        scene.add_object(Object::new(
+            Sphere::new(Vec3::new(5.0, 0.0, 0.0), 0.5),
+            Lambertian::new(color::WHITE),
+        ));
+        scene.add_area_light(
+            Object::new(
+                Sphere::new(Vec3::new(0.0, 3.0, 0.0), 0.5),
+                Emissive::new(color::WHITE, 1.0),
+            ),
+            16,
+        );
+
+        scene.remove_object(0);
+        assert!(scene.is_area_light(0));
+        assert!(average_light(&scene, Vec3::ZERO).g > 0.0);
+
+        scene.remove_object(0);
+        assert_eq!(scene.lights().count(), 0);
+    }
+
+    #[test]
+    fn sample_pdf_matches_pdf() {
+        let scene = lit_scene();
+        let light = scene.lights().next().unwrap();
+        let generator = RandomSampler::new(1);
+        let location = Vec3::new(0.3, 0.0, 0.2);
+
+        for sample_number in 0..8 {
+            let mut sampler = SampleStream::new(&generator, 0, sample_number);
+            let sample = light.sample(&scene, location, &mut sampler).unwrap();
+            let pdf = light.pdf(&scene, location, sample.direction.unwrap());
+
+            assert!((sample.pdf - pdf).abs() <= 1e-3 * pdf);
+        }
+
+        assert_eq!(light.pdf(&scene, location, Vec3::NEG_Y), 0.0);
+    }
+}
@@ -1,8 +1,8 @@
 use glam::Vec3;
 
-use crate::{color::Color, ray::Ray};
+use crate::{color::Color, sampling::SampleStream, scene::description::LightDescription};
 
-use super::{Light, LightRay};
+use super::{Light, LightSample};
 
 #[derive(Debug, Clone, Copy)]
 pub struct DirectionalLight {
@@ -20,16 +20,25 @@ impl DirectionalLight {
 }
 
 impl Light for DirectionalLight {
-    fn light_at(&self, scene: &crate::scene::Scene, location: Vec3) -> Option<super::LightRay> {
-        match scene.any_hit(&Ray {
-            origin: location,
-            direction: -self.direction,
-        }) {
-            Some(_) => None,
-            None => Some(LightRay {
-                direction: Some(-self.direction),
-                color: self.color,
-            }),
-        }
+    fn sample(
+        &self,
+        _scene: &crate::scene::Scene,
+        _location: Vec3,
+        _sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        Some(LightSample {
+            direction: Some(-self.direction),
+            radiance: self.color,
+            pdf: 1.0,
+            distance: f32::MAX,
+            is_delta: true,
+        })
+    }
+
+    fn describe(&self) -> Option<LightDescription> {
+        Some(LightDescription::Directional {
+            color: self.color,
+            direction: self.direction,
+        })
     }
 }
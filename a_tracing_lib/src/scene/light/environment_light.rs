@@ -0,0 +1,33 @@
+use glam::Vec3;
+
+use crate::{sampling::SampleStream, scene::Scene};
+
+use super::{Light, LightSample};
+
+/// Samples the background of the scene as a light, picking directions proportional to its brightness
+/// Not stored with the lights of a scene, ```Scene::sampled_lights``` includes it while the background is sampled
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvironmentLight;
+
+impl Light for EnvironmentLight {
+    fn sample(
+        &self,
+        scene: &Scene,
+        _location: Vec3,
+        sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        let (direction, pdf) = scene.background.sample_direction(sampler.next_vec2())?;
+
+        Some(LightSample {
+            direction: Some(direction),
+            radiance: scene.background.color(direction),
+            pdf,
+            distance: f32::MAX,
+            is_delta: false,
+        })
+    }
+
+    fn pdf(&self, scene: &Scene, _location: Vec3, direction: Vec3) -> f32 {
+        scene.background.pdf(direction)
+    }
+}
@@ -1,16 +1,99 @@
 pub mod ambient_light;
+pub mod area_light;
 pub mod directional_light;
+pub mod environment_light;
+pub mod point_light;
 
-use super::Scene;
-use crate::color::Color;
+use super::{description::LightDescription, Scene};
+use crate::{color::Color, ray::Ray, sampling::SampleStream};
+pub use area_light::AreaLight;
 pub use directional_light::DirectionalLight;
+pub use environment_light::EnvironmentLight;
 use glam::Vec3;
+pub use point_light::{PointLight, SpotLight};
+
+/// Shadow rays towards a sampled point stop this fraction of the distance short of it,
+/// so they don't hit the surface of an area light itself
+const SHADOW_RAY_LENGTH: f32 = 0.999;
 
 pub struct LightRay {
     pub direction: Option<Vec3>,
     pub color: Color,
 }
 
-pub trait Light {
-    fn light_at(&self, scene: &Scene, location: Vec3) -> Option<LightRay>;
+/// A direction towards a light picked by ```Light::sample```, without checking whether anything is in the way
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Normalized direction towards the light, None for light arriving from everywhere such as ambient light
+    pub direction: Option<Vec3>,
+    /// Light arriving along the direction
+    pub radiance: Color,
+    /// Solid angle pdf of the direction, 1 for lights that only arrive from a single direction
+    pub pdf: f32,
+    /// Distance to the sampled point of the light, shadow rays stop short of it
+    pub distance: f32,
+    /// Point, spot, directional and ambient lights can't be hit by rays, so material sampling never finds them
+    pub is_delta: bool,
+}
+
+pub trait Light: Send + Sync {
+    /// Pick a direction towards the light as seen from the location, None if the light doesn't reach it
+    /// Lights with an extent pick a point on their surface using the sampler
+    fn sample(
+        &self,
+        scene: &Scene,
+        location: Vec3,
+        sampler: &mut SampleStream,
+    ) -> Option<LightSample>;
+
+    /// Solid angle pdf with which ```sample``` picks the direction from the location
+    /// Zero for delta lights and for directions that miss the light
+    fn pdf(&self, _scene: &Scene, _location: Vec3, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Light arriving at the location, None if the light is blocked or doesn't reach it
+    /// The color of lights with an extent is divided by the pdf of the sampled direction
+    fn light_at(
+        &self,
+        scene: &Scene,
+        location: Vec3,
+        sampler: &mut SampleStream,
+    ) -> Option<LightRay> {
+        let sample = self.sample(scene, location, sampler)?;
+        if sample.pdf <= 0.0 || is_occluded(scene, location, &sample) {
+            return None;
+        }
+
+        Some(LightRay {
+            direction: sample.direction,
+            color: sample.radiance / sample.pdf,
+        })
+    }
+
+    /// Number of times the light is sampled and averaged per shading point
+    /// Only lights with an extent benefit from more than one, more samples give smoother soft shadows
+    fn sample_count(&self) -> u32 {
+        1
+    }
+
+    /// Description used to save the light to a scene file, lights without one can't be saved
+    fn describe(&self) -> Option<LightDescription> {
+        None
+    }
+}
+
+/// Returns true if an object lies between the location and the sampled point of the light
+pub fn is_occluded(scene: &Scene, location: Vec3, sample: &LightSample) -> bool {
+    let Some(direction) = sample.direction else {
+        return false;
+    };
+
+    let shadow_ray = Ray {
+        origin: location,
+        direction,
+    };
+    scene
+        .any_hit(&shadow_ray, sample.distance * SHADOW_RAY_LENGTH)
+        .is_some()
 }
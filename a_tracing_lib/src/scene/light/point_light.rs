@@ -0,0 +1,227 @@
+use glam::Vec3;
+
+use crate::{
+    color::Color,
+    sampling::SampleStream,
+    scene::{description::LightDescription, Scene},
+};
+
+use super::{Light, LightSample};
+
+/// Light emitted equally in every direction from a single point
+/// The arriving light falls off with the inverse square of the distance
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    /// Radiant intensity, the light arriving at a distance of 1
+    intensity: Color,
+    position: Vec3,
+}
+
+impl PointLight {
+    pub fn new(intensity: Color, position: Vec3) -> Self {
+        Self {
+            intensity,
+            position,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(
+        &self,
+        _scene: &Scene,
+        location: Vec3,
+        _sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        point_sample(location, self.position, self.intensity)
+    }
+
+    fn describe(&self) -> Option<LightDescription> {
+        Some(LightDescription::Point {
+            intensity: self.intensity,
+            position: self.position,
+        })
+    }
+}
+
+/// Light emitted from a single point into a cone around the direction
+/// The intensity fades out smoothly between the inner and the outer cone angle
+#[derive(Debug, Clone, Copy)]
+pub struct SpotLight {
+    /// Radiant intensity inside the inner cone
+    intensity: Color,
+    position: Vec3,
+    direction: Vec3,
+    /// Half angles of the cones in radians
+    inner_angle: f32,
+    outer_angle: f32,
+}
+
+impl SpotLight {
+    /// The angles are measured from the direction to the edge of the cone in radians
+    pub fn new(
+        intensity: Color,
+        position: Vec3,
+        direction: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        assert!(
+            0.0 <= inner_angle && inner_angle <= outer_angle,
+            "The inner angle of a spot light has to lie between 0 and the outer angle."
+        );
+
+        Self {
+            intensity,
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+        }
+    }
+
+    /// Fraction of the intensity emitted in the given direction away from the light
+    fn falloff(&self, direction: Vec3) -> f32 {
+        let cos_theta = direction.dot(self.direction);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+
+        if cos_theta >= cos_inner {
+            return 1.0;
+        }
+
+        if cos_theta <= cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(
+        &self,
+        _scene: &Scene,
+        location: Vec3,
+        _sampler: &mut SampleStream,
+    ) -> Option<LightSample> {
+        let to_location = (location - self.position).normalize();
+        let falloff = self.falloff(to_location);
+
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        point_sample(location, self.position, self.intensity * falloff)
+    }
+
+    fn describe(&self) -> Option<LightDescription> {
+        Some(LightDescription::Spot {
+            intensity: self.intensity,
+            position: self.position,
+            direction: self.direction,
+            inner_angle_degrees: self.inner_angle.to_degrees(),
+            outer_angle_degrees: self.outer_angle.to_degrees(),
+        })
+    }
+}
+
+/// Sample of a light at the position emitting the intensity towards the location, falling off with the squared distance
+fn point_sample(location: Vec3, position: Vec3, intensity: Color) -> Option<LightSample> {
+    let to_light = position - location;
+    let distance = to_light.length();
+
+    if distance == 0.0 {
+        return None;
+    }
+
+    Some(LightSample {
+        direction: Some(to_light / distance),
+        radiance: intensity / (distance * distance),
+        pdf: 1.0,
+        distance,
+        is_delta: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::Vec3;
+
+    use crate::{
+        color,
+        sampling::{RandomSampler, SampleStream},
+        scene::{
+            camera::PerspectiveCamera,
+            light::{Light, LightRay},
+            material::Lambertian,
+            object::Object,
+            sphere::Sphere,
+            Scene,
+        },
+    };
+
+    use super::{PointLight, SpotLight};
+
+    fn empty_scene() -> Scene {
+        Scene::new(PerspectiveCamera::default())
+    }
+
+    fn light_at(light: &dyn Light, scene: &Scene, location: Vec3) -> Option<LightRay> {
+        let generator = RandomSampler::new(0);
+        light.light_at(scene, location, &mut SampleStream::new(&generator, 0, 0))
+    }
+
+    #[test]
+    fn point_light_inverse_square() {
+        let scene = empty_scene();
+        let light = PointLight::new(color::WHITE * 8.0, Vec3::new(0.0, 2.0, 0.0));
+
+        let light_ray = light_at(&light, &scene, Vec3::ZERO).unwrap();
+        assert_eq!(light_ray.direction, Some(Vec3::Y));
+        assert!((light_ray.color.r - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shadow_rays_stop_at_the_light() {
+        let mut scene = empty_scene();
+        scene.add_object(Object {
+            shape: Box::new(Sphere::new(Vec3::new(0.0, 5.0, 0.0), 1.0)),
+            material: Arc::new(Lambertian::new(color::WHITE)),
+        });
+
+        // The sphere lies behind the near light but blocks the far one
+        let near = PointLight::new(color::WHITE, Vec3::new(0.0, 2.0, 0.0));
+        let far = PointLight::new(color::WHITE, Vec3::new(0.0, 10.0, 0.0));
+
+        assert!(light_at(&near, &scene, Vec3::ZERO).is_some());
+        assert!(light_at(&far, &scene, Vec3::ZERO).is_none());
+    }
+
+    #[test]
+    fn spot_light_cone_falloff() {
+        let scene = empty_scene();
+        let light = SpotLight::new(
+            color::WHITE,
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::NEG_Y,
+            20f32.to_radians(),
+            40f32.to_radians(),
+        );
+
+        let at_angle = |degrees: f32| {
+            let location = Vec3::new(degrees.to_radians().tan(), 0.0, 0.0);
+            let distance = (location - Vec3::Y).length();
+            light_at(&light, &scene, location)
+                .map_or(0.0, |light_ray| light_ray.color.r * distance * distance)
+        };
+
+        assert!((at_angle(10.0) - 1.0).abs() < 1e-4);
+        assert!(at_angle(30.0) > 0.0 && at_angle(30.0) < 1.0);
+        assert!(at_angle(35.0) < at_angle(25.0));
+        assert_eq!(at_angle(50.0), 0.0);
+    }
+}
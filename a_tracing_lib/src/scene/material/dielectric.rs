@@ -0,0 +1,92 @@
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::SampleStream,
+    scene::description::MaterialDescription,
+};
+
+use super::{reflect, Material, ScatterRecord};
+
+/// Transparent material such as glass or water that both reflects and refracts light
+#[derive(Debug, Clone, Copy)]
+pub struct Dielectric {
+    /// Index of refraction, 1.5 for glass
+    pub ior: f32,
+    pub tint: Color,
+}
+
+impl Dielectric {
+    pub fn new(ior: f32) -> Self {
+        Self::with_tint(ior, color::WHITE)
+    }
+
+    pub fn with_tint(ior: f32, tint: Color) -> Self {
+        assert!(ior > 0.0);
+
+        Self { ior, tint }
+    }
+}
+
+/// Schlick's approximation of the fresnel reflectance
+fn reflectance(cos_theta: f32, eta: f32) -> f32 {
+    let r0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos_theta).powi(5)
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        // The normal points out of the object, so a viewer on that side means the light enters the object
+        let entering = record.normal.dot(wo) > 0.0;
+        let (normal, eta) = if entering {
+            (record.normal, 1.0 / self.ior)
+        } else {
+            (-record.normal, self.ior)
+        };
+
+        let cos_theta = normal.dot(wo).min(1.0);
+        let sin_theta_squared = (1.0 - cos_theta * cos_theta).max(0.0);
+        let total_internal_reflection = eta * eta * sin_theta_squared > 1.0;
+
+        let direction =
+            if total_internal_reflection || sampler.next_f32() < reflectance(cos_theta, eta) {
+                reflect(wo, normal)
+            } else {
+                let perpendicular = eta * (normal * cos_theta - wo);
+                let parallel = -(1.0 - perpendicular.length_squared()).abs().sqrt() * normal;
+                (perpendicular + parallel).normalize()
+            };
+
+        Some(ScatterRecord {
+            direction,
+            attenuation: self.tint,
+            pdf: 1.0,
+            is_specular: true,
+        })
+    }
+
+    fn evaluate(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> Color {
+        color::BLACK
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> f32 {
+        0.0
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.tint
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Dielectric {
+            ior: self.ior,
+            tint: self.tint,
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::SampleStream,
+    scene::description::MaterialDescription,
+};
+
+use super::{Material, ScatterRecord};
+
+/// Surface that emits light from the side its normal points to and doesn't reflect any light
+#[derive(Debug, Clone, Copy)]
+pub struct Emissive {
+    pub color: Color,
+    pub strength: f32,
+}
+
+impl Emissive {
+    pub fn new(color: Color, strength: f32) -> Self {
+        Self { color, strength }
+    }
+}
+
+impl Material for Emissive {
+    fn scatter(
+        &self,
+        _wo: Vec3,
+        _record: &HitRecord,
+        _sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn evaluate(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> Color {
+        color::BLACK
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> f32 {
+        0.0
+    }
+
+    fn emitted(&self, wo: Vec3, record: &HitRecord) -> Color {
+        if record.normal.dot(wo) > 0.0 {
+            self.color * self.strength
+        } else {
+            color::BLACK
+        }
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.color
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Emissive {
+            color: self.color,
+            strength: self.strength,
+        })
+    }
+}
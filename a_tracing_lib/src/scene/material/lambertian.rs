@@ -0,0 +1,70 @@
+use std::f32::consts::FRAC_1_PI;
+
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::{cosine_sample_hemisphere, SampleStream},
+    scene::description::MaterialDescription,
+};
+
+use super::{facing_normal, same_hemisphere, Material, ScatterRecord};
+
+/// Perfectly diffuse surface reflecting light equally in all directions
+#[derive(Debug, Clone, Copy)]
+pub struct Lambertian {
+    pub albedo: Color,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        let normal = facing_normal(record.normal, wo);
+        let direction = cosine_sample_hemisphere(normal, sampler.next_vec2());
+
+        Some(ScatterRecord {
+            direction,
+            // The brdf and cosine term cancel against the cosine weighted pdf
+            attenuation: self.albedo,
+            pdf: normal.dot(direction).max(0.0) * FRAC_1_PI,
+            is_specular: false,
+        })
+    }
+
+    fn evaluate(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> Color {
+        if same_hemisphere(record.normal, wo, wi) {
+            self.albedo * FRAC_1_PI
+        } else {
+            color::BLACK
+        }
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> f32 {
+        if same_hemisphere(record.normal, wo, wi) {
+            record.normal.dot(wi).abs() * FRAC_1_PI
+        } else {
+            0.0
+        }
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Lambertian {
+            albedo: self.albedo,
+        })
+    }
+}
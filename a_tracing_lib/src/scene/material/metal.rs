@@ -0,0 +1,72 @@
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::{uniform_sample_sphere, SampleStream},
+    scene::description::MaterialDescription,
+};
+
+use super::{facing_normal, reflect, Material, ScatterRecord};
+
+/// Mirror-like surface, the roughness blurs the reflection by randomly offsetting the mirrored direction
+#[derive(Debug, Clone, Copy)]
+pub struct Metal {
+    pub albedo: Color,
+    /// 0.0 gives a perfect mirror
+    pub roughness: f32,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, roughness: f32) -> Self {
+        Self {
+            albedo,
+            roughness: roughness.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        let normal = facing_normal(record.normal, wo);
+        let mirrored = reflect(wo, normal);
+        let direction =
+            (mirrored + self.roughness * uniform_sample_sphere(sampler.next_vec2())).normalize();
+
+        // Offset directions that end up below the surface are absorbed
+        if direction.dot(normal) <= 0.0 {
+            return None;
+        }
+
+        Some(ScatterRecord {
+            direction,
+            attenuation: self.albedo,
+            pdf: 1.0,
+            is_specular: true,
+        })
+    }
+
+    fn evaluate(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> Color {
+        color::BLACK
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3, _record: &HitRecord) -> f32 {
+        0.0
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Metal {
+            albedo: self.albedo,
+            roughness: self.roughness,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    f32::consts::{FRAC_1_PI, PI, TAU},
+    sync::Arc,
+};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::{cosine_sample_hemisphere, SampleStream},
+    scene::description::{MaterialDescription, TextureDescription},
+};
+
+use super::{
+    super::texture::Texture, facing_normal, reflect, same_hemisphere, Material, ScatterRecord,
+};
+
+/// Smallest GGX alpha, perfectly smooth surfaces make the distribution numerically unstable
+const MIN_ALPHA: f32 = 1e-3;
+
+/// Reflectance at normal incidence of dielectrics in the metallic-roughness model
+const DIELECTRIC_F0: f32 = 0.04;
+
+/// Physically based Cook-Torrance material with a GGX microfacet distribution
+/// using the metallic-roughness parametrisation of glTF
+#[derive(Clone)]
+pub struct PbrMaterial {
+    pub base_color: Color,
+    /// 0.0 is a dielectric with a diffuse base, 1.0 a metal tinted by the base color
+    pub metallic: f32,
+    /// Perceptual roughness, the GGX alpha is its square
+    pub roughness: f32,
+    /// Light emitted by the surface
+    pub emission: Color,
+    /// Multiplied with the base color
+    pub base_color_texture: Option<Arc<Texture>>,
+    /// Roughness in the green and metallic in the blue channel, multiplied with the factors
+    pub metallic_roughness_texture: Option<Arc<Texture>>,
+}
+
+/// The material parameters at a single point of the surface
+struct Surface {
+    base_color: Color,
+    metallic: f32,
+    roughness: f32,
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: Color, metallic: f32, roughness: f32) -> Self {
+        Self {
+            base_color,
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+            emission: color::BLACK,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+        }
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_base_color_texture(mut self, texture: Arc<Texture>) -> Self {
+        self.base_color_texture = Some(texture);
+        self
+    }
+
+    pub fn with_metallic_roughness_texture(mut self, texture: Arc<Texture>) -> Self {
+        self.metallic_roughness_texture = Some(texture);
+        self
+    }
+
+    fn surface(&self, record: &HitRecord) -> Surface {
+        let base_color = match &self.base_color_texture {
+            Some(texture) => self.base_color * texture.sample(record.uv),
+            None => self.base_color,
+        };
+
+        let (metallic, roughness) = match &self.metallic_roughness_texture {
+            Some(texture) => {
+                let texel = texture.sample(record.uv);
+                (self.metallic * texel.b, self.roughness * texel.g)
+            }
+            None => (self.metallic, self.roughness),
+        };
+
+        Surface {
+            base_color,
+            metallic,
+            roughness,
+        }
+    }
+}
+
+impl Surface {
+    fn alpha(&self) -> f32 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    /// Reflectance at normal incidence
+    fn f0(&self) -> Color {
+        Color::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0).lerp(self.base_color, self.metallic)
+    }
+
+    fn diffuse_color(&self) -> Color {
+        self.base_color * (1.0 - self.metallic)
+    }
+
+    /// Probability of sampling the specular lobe instead of the diffuse one for the given viewing angle
+    fn specular_probability(&self, cos_theta_o: f32) -> f32 {
+        let specular = fresnel_schlick(self.f0(), cos_theta_o).max_component();
+        let diffuse = self.diffuse_color().max_component();
+
+        if specular + diffuse <= 0.0 {
+            return 1.0;
+        }
+
+        (specular / (specular + diffuse)).clamp(0.1, 1.0)
+    }
+}
+
+/// Express a direction in the local frame where the normal is the z axis
+fn to_local(direction: Vec3, tangent: Vec3, bitangent: Vec3, normal: Vec3) -> Vec3 {
+    Vec3::new(
+        direction.dot(tangent),
+        direction.dot(bitangent),
+        direction.dot(normal),
+    )
+}
+
+fn fresnel_schlick(f0: Color, cos_theta: f32) -> Color {
+    f0 + (color::WHITE - f0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+/// GGX normal distribution function for the cosine between the normal and the half vector
+fn ggx_distribution(cos_theta_h: f32, alpha: f32) -> f32 {
+    let alpha_squared = alpha * alpha;
+    let denominator = cos_theta_h * cos_theta_h * (alpha_squared - 1.0) + 1.0;
+    alpha_squared / (PI * denominator * denominator)
+}
+
+/// Smith masking function of the GGX distribution for a direction with the given cosine to the normal
+fn smith_g1(cos_theta: f32, alpha: f32) -> f32 {
+    let alpha_squared = alpha * alpha;
+    let cos_squared = cos_theta * cos_theta;
+    2.0 * cos_theta / (cos_theta + (alpha_squared + (1.0 - alpha_squared) * cos_squared).sqrt())
+}
+
+/// Sample a half vector from the distribution of normals visible from the local direction wo
+/// Heitz, "Sampling the GGX Distribution of Visible Normals", 2018
+fn sample_visible_normal(wo: Vec3, alpha: f32, sample: Vec2) -> Vec3 {
+    // Stretch the view direction to the hemisphere configuration
+    let view = Vec3::new(alpha * wo.x, alpha * wo.y, wo.z).normalize();
+
+    let length_squared = view.x * view.x + view.y * view.y;
+    let t1 = if length_squared > 0.0 {
+        Vec3::new(-view.y, view.x, 0.0) / length_squared.sqrt()
+    } else {
+        Vec3::X
+    };
+    let t2 = view.cross(t1);
+
+    // Sample a point on the projected hemisphere
+    let radius = sample.x.sqrt();
+    let phi = TAU * sample.y;
+    let p1 = radius * phi.cos();
+    let s = 0.5 * (1.0 + view.z);
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * radius * phi.sin();
+
+    let normal = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * view;
+
+    // Unstretch back to the ellipsoid configuration
+    Vec3::new(alpha * normal.x, alpha * normal.y, normal.z.max(0.0)).normalize()
+}
+
+impl Material for PbrMaterial {
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        let normal = facing_normal(record.normal, wo);
+        let surface = self.surface(record);
+        let sample = sampler.next_vec2();
+
+        let direction = if sampler.next_f32() < surface.specular_probability(normal.dot(wo)) {
+            let (tangent, bitangent) = normal.any_orthonormal_pair();
+            let wo_local = to_local(wo, tangent, bitangent, normal);
+            let h = sample_visible_normal(wo_local, surface.alpha(), sample);
+            let half_vector = h.x * tangent + h.y * bitangent + h.z * normal;
+
+            reflect(wo, half_vector)
+        } else {
+            cosine_sample_hemisphere(normal, sample)
+        };
+
+        let pdf = self.pdf(wo, direction, record);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let cos_theta = normal.dot(direction);
+        Some(ScatterRecord {
+            direction,
+            attenuation: self.evaluate(wo, direction, record) * (cos_theta / pdf),
+            pdf,
+            is_specular: false,
+        })
+    }
+
+    fn evaluate(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> Color {
+        if !same_hemisphere(record.normal, wo, wi) {
+            return color::BLACK;
+        }
+
+        let normal = facing_normal(record.normal, wo);
+        let cos_theta_o = normal.dot(wo);
+        let cos_theta_i = normal.dot(wi);
+        if cos_theta_o <= 0.0 || cos_theta_i <= 0.0 {
+            return color::BLACK;
+        }
+
+        let surface = self.surface(record);
+        let half_vector = (wo + wi).normalize();
+        let alpha = surface.alpha();
+
+        let fresnel = fresnel_schlick(surface.f0(), wi.dot(half_vector));
+        let distribution = ggx_distribution(normal.dot(half_vector), alpha);
+        let masking = smith_g1(cos_theta_o, alpha) * smith_g1(cos_theta_i, alpha);
+
+        let specular = fresnel * (distribution * masking / (4.0 * cos_theta_o * cos_theta_i));
+        let diffuse = (color::WHITE - fresnel) * surface.diffuse_color() * FRAC_1_PI;
+
+        diffuse + specular
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> f32 {
+        if !same_hemisphere(record.normal, wo, wi) {
+            return 0.0;
+        }
+
+        let normal = facing_normal(record.normal, wo);
+        let cos_theta_o = normal.dot(wo);
+        if cos_theta_o <= 0.0 {
+            return 0.0;
+        }
+
+        let surface = self.surface(record);
+        let half_vector = (wo + wi).normalize();
+        let alpha = surface.alpha();
+
+        // Visible normal pdf converted from half vectors to reflected directions
+        let specular_pdf = smith_g1(cos_theta_o, alpha)
+            * ggx_distribution(normal.dot(half_vector), alpha)
+            / (4.0 * cos_theta_o);
+        let diffuse_pdf = normal.dot(wi).max(0.0) * FRAC_1_PI;
+
+        let specular_probability = surface.specular_probability(cos_theta_o);
+        specular_probability * specular_pdf + (1.0 - specular_probability) * diffuse_pdf
+    }
+
+    fn emitted(&self, wo: Vec3, record: &HitRecord) -> Color {
+        if record.normal.dot(wo) > 0.0 {
+            self.emission
+        } else {
+            color::BLACK
+        }
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.surface(record).base_color
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Pbr {
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            emission: self.emission,
+            base_color_texture: self
+                .base_color_texture
+                .as_deref()
+                .map(TextureDescription::new),
+            metallic_roughness_texture: self
+                .metallic_roughness_texture
+                .as_deref()
+                .map(TextureDescription::new),
+        })
+    }
+}
@@ -0,0 +1,159 @@
+pub mod dielectric;
+pub mod emissive;
+pub mod lambertian;
+pub mod metal;
+pub mod microfacet;
+pub mod phong;
+
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::SampleStream,
+    scene::description::MaterialDescription,
+};
+
+pub use dielectric::Dielectric;
+pub use emissive::Emissive;
+pub use lambertian::Lambertian;
+pub use metal::Metal;
+pub use microfacet::PbrMaterial;
+pub use phong::Phong;
+
+/// A sampled direction in which light scatters off a surface
+#[derive(Debug, Clone, Copy)]
+pub struct ScatterRecord {
+    /// Direction the scattered light arrives from, pointing away from the surface
+    pub direction: Vec3,
+    /// Brdf times the cosine term divided by the pdf, the factor a path throughput gets multiplied with
+    pub attenuation: Color,
+    /// Solid angle pdf of the direction, meaningless for specular scattering
+    pub pdf: f32,
+    /// Specular scattering only happens in the sampled direction, so ```evaluate``` and ```pdf``` can't be used for it
+    pub is_specular: bool,
+}
+
+/// Describes how light interacts with the surface of an object
+/// All directions point away from the surface, wo is the direction towards the viewer and wi the direction towards the light
+pub trait Material: Send + Sync {
+    /// Sample a direction wi from which light scatters towards wo
+    /// Returns None when the light is absorbed
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord>;
+
+    /// Value of the brdf for the given directions, without the cosine term
+    fn evaluate(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> Color;
+
+    /// Solid angle pdf with which ```scatter``` samples wi for the given wo
+    fn pdf(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> f32;
+
+    /// Light emitted by the surface itself towards wo
+    fn emitted(&self, _wo: Vec3, _record: &HitRecord) -> Color {
+        color::BLACK
+    }
+
+    /// Overall surface color at the hit point, used by the quick render and ambient light
+    fn albedo(&self, record: &HitRecord) -> Color;
+
+    /// Description used to save the material to a scene file, materials without one can't be saved
+    fn describe(&self) -> Option<MaterialDescription> {
+        None
+    }
+}
+
+/// The surface normal flipped to the side of the given direction
+pub fn facing_normal(normal: Vec3, direction: Vec3) -> Vec3 {
+    if normal.dot(direction) < 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+/// Mirror the direction around the normal, both pointing away from the surface
+pub(crate) fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    2.0 * direction.dot(normal) * normal - direction
+}
+
+/// Returns true if both directions lie on the same side of the surface
+pub(crate) fn same_hemisphere(normal: Vec3, a: Vec3, b: Vec3) -> bool {
+    normal.dot(a) * normal.dot(b) > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use crate::{
+        color,
+        ray::HitRecord,
+        sampling::{RandomSampler, SampleStream},
+    };
+
+    use super::{Lambertian, Material, PbrMaterial, Phong};
+
+    fn record() -> HitRecord {
+        HitRecord {
+            point: Vec3::ZERO,
+            normal: Vec3::Y,
+            t: 1.0,
+            uv: Vec2::ZERO,
+            barycentric: Vec2::ZERO,
+        }
+    }
+
+    fn assert_scatter_matches_pdf(material: &dyn Material) {
+        let record = record();
+        let wo = Vec3::new(0.3, 1.0, -0.2).normalize();
+        let generator = RandomSampler::new(3);
+        let mut sampler = SampleStream::new(&generator, 0, 0);
+
+        for _ in 0..100 {
+            if let Some(scatter) = material.scatter(wo, &record, &mut sampler) {
+                let pdf = material.pdf(wo, scatter.direction, &record);
+                assert!((scatter.pdf - pdf).abs() <= 1e-3 * pdf.max(1.0));
+                assert!(scatter.direction.dot(record.normal) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lambertian_scatter_pdf() {
+        assert_scatter_matches_pdf(&Lambertian::new(color::WHITE));
+    }
+
+    #[test]
+    fn test_phong_scatter_pdf() {
+        assert_scatter_matches_pdf(&Phong::new(color::RED * 0.5, color::WHITE * 0.5, 20.0));
+    }
+
+    #[test]
+    fn test_pbr_scatter_pdf() {
+        assert_scatter_matches_pdf(&PbrMaterial::new(color::GREEN, 0.0, 0.5));
+        assert_scatter_matches_pdf(&PbrMaterial::new(color::WHITE, 1.0, 0.2));
+    }
+
+    #[test]
+    fn test_pbr_white_furnace() {
+        // A white rough metal can't reflect more light than it receives
+        let material = PbrMaterial::new(color::WHITE, 1.0, 0.6);
+        let record = record();
+        let wo = Vec3::new(0.5, 1.0, 0.0).normalize();
+        let generator = RandomSampler::new(11);
+        let mut sampler = SampleStream::new(&generator, 0, 0);
+
+        let count = 10000;
+        let reflected = (0..count)
+            .filter_map(|_| material.scatter(wo, &record, &mut sampler))
+            .map(|scatter| scatter.attenuation.r)
+            .sum::<f32>()
+            / count as f32;
+
+        assert!(reflected > 0.8 && reflected <= 1.01, "{reflected}");
+    }
+}
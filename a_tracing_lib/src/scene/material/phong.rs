@@ -0,0 +1,128 @@
+use std::f32::consts::{FRAC_1_PI, PI, TAU};
+
+use glam::Vec3;
+
+use crate::{
+    color::{self, Color},
+    ray::HitRecord,
+    sampling::{cosine_sample_hemisphere, SampleStream},
+    scene::description::MaterialDescription,
+};
+
+use super::{facing_normal, reflect, same_hemisphere, Material, ScatterRecord};
+
+/// Diffuse surface with a glossy highlight using the energy normalized Blinn-Phong model
+#[derive(Debug, Clone, Copy)]
+pub struct Phong {
+    pub diffuse: Color,
+    pub specular: Color,
+    /// Higher exponents give smaller and sharper highlights
+    pub exponent: f32,
+}
+
+impl Phong {
+    pub fn new(diffuse: Color, specular: Color, exponent: f32) -> Self {
+        assert!(exponent >= 0.0);
+
+        Self {
+            diffuse,
+            specular,
+            exponent,
+        }
+    }
+
+    /// Probability of sampling the specular lobe instead of the diffuse one
+    fn specular_weight(&self) -> f32 {
+        let diffuse = self.diffuse.max_component();
+        let specular = self.specular.max_component();
+
+        if diffuse + specular <= 0.0 {
+            0.0
+        } else {
+            specular / (diffuse + specular)
+        }
+    }
+}
+
+impl Material for Phong {
+    fn scatter(
+        &self,
+        wo: Vec3,
+        record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> Option<ScatterRecord> {
+        let normal = facing_normal(record.normal, wo);
+        let sample = sampler.next_vec2();
+
+        let direction = if sampler.next_f32() < self.specular_weight() {
+            // Sample the half vector proportional to cos^exponent around the normal
+            let cos_theta = sample.x.powf(1.0 / (self.exponent + 1.0));
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = TAU * sample.y;
+
+            let (tangent, bitangent) = normal.any_orthonormal_pair();
+            let half_vector = sin_theta * phi.cos() * tangent
+                + sin_theta * phi.sin() * bitangent
+                + cos_theta * normal;
+
+            reflect(wo, half_vector)
+        } else {
+            cosine_sample_hemisphere(normal, sample)
+        };
+
+        let pdf = self.pdf(wo, direction, record);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let cos_theta = normal.dot(direction);
+        Some(ScatterRecord {
+            direction,
+            attenuation: self.evaluate(wo, direction, record) * (cos_theta / pdf),
+            pdf,
+            is_specular: false,
+        })
+    }
+
+    fn evaluate(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> Color {
+        if !same_hemisphere(record.normal, wo, wi) {
+            return color::BLACK;
+        }
+
+        let normal = facing_normal(record.normal, wo);
+        let half_vector = (wo + wi).normalize();
+        let n_dot_h = normal.dot(half_vector).max(0.0);
+        let normalization = (self.exponent + 8.0) / (8.0 * PI);
+
+        self.diffuse * FRAC_1_PI + self.specular * (normalization * n_dot_h.powf(self.exponent))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, record: &HitRecord) -> f32 {
+        if !same_hemisphere(record.normal, wo, wi) {
+            return 0.0;
+        }
+
+        let normal = facing_normal(record.normal, wo);
+        let diffuse_pdf = normal.dot(wi).max(0.0) * FRAC_1_PI;
+
+        let half_vector = (wo + wi).normalize();
+        let n_dot_h = normal.dot(half_vector).max(0.0);
+        let half_vector_pdf = (self.exponent + 1.0) / TAU * n_dot_h.powf(self.exponent);
+        let specular_pdf = half_vector_pdf / (4.0 * wo.dot(half_vector)).max(f32::EPSILON);
+
+        let specular_weight = self.specular_weight();
+        specular_weight * specular_pdf + (1.0 - specular_weight) * diffuse_pdf
+    }
+
+    fn albedo(&self, _record: &HitRecord) -> Color {
+        self.diffuse + self.specular
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Phong {
+            diffuse: self.diffuse,
+            specular: self.specular,
+            exponent: self.exponent,
+        })
+    }
+}
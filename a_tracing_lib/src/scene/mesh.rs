@@ -0,0 +1,249 @@
+use std::sync::OnceLock;
+
+use glam::{Vec2, Vec3};
+
+use crate::ray::{HitRecord, Ray};
+
+use super::{
+    aabb::Aabb,
+    bvh::Bvh,
+    description::ShapeDescription,
+    object::Shape,
+    triangle::{intersect_triangle, sample_triangle, triangle_area},
+};
+
+/// Mesh of triangles sharing their vertex data
+/// Every triangle is a triple of indices into the shared position, normal and uv buffers
+/// The triangles are stored in their own bounding volume hierarchy which is built on creation
+pub struct TriangleMesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<[u32; 3]>,
+    bvh: Bvh,
+    /// Running sum of the triangle areas, built when the mesh is first sampled as a light
+    area_sums: OnceLock<Vec<f32>>,
+}
+
+impl TriangleMesh {
+    /// Create a mesh using flat shading and no texture coordinates
+    pub fn new(positions: Vec<Vec3>, indices: Vec<[u32; 3]>) -> Self {
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&index| (index as usize) < positions.len()),
+            "Triangle mesh index out of bounds."
+        );
+
+        let triangle_bounds: Vec<_> = indices
+            .iter()
+            .map(|triangle| Aabb::from_points(triangle.map(|vertex| positions[vertex as usize])))
+            .collect();
+        let bvh = Bvh::build(&triangle_bounds);
+
+        Self {
+            positions,
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices,
+            bvh,
+            area_sums: OnceLock::new(),
+        }
+    }
+
+    /// Use the given per-vertex normals, they are interpolated over every triangle for smooth shading
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(normals.len(), self.positions.len());
+
+        self.normals = normals
+            .into_iter()
+            .map(|normal| normal.normalize())
+            .collect();
+        self
+    }
+
+    /// Use the given per-vertex texture coordinates
+    pub fn with_uvs(mut self, uvs: Vec<Vec2>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len());
+
+        self.uvs = uvs;
+        self
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+
+    pub fn uvs(&self) -> &[Vec2] {
+        &self.uvs
+    }
+
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Positions of the three vertices of the given triangle
+    pub fn triangle(&self, index: usize) -> [Vec3; 3] {
+        self.indices[index].map(|vertex| self.positions[vertex as usize])
+    }
+
+    /// Intersect a single triangle of the mesh
+    fn hit_triangle(&self, index: usize, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t, barycentric) = intersect_triangle(ray, self.triangle(index), t_min, t_max)?;
+        Some(self.surface_record(index, barycentric, ray.at(t), t))
+    }
+
+    /// Surface attributes of the point with the given barycentric coordinates on a triangle
+    fn surface_record(&self, index: usize, barycentric: Vec2, point: Vec3, t: f32) -> HitRecord {
+        let [i0, i1, i2] = self.indices[index].map(|vertex| vertex as usize);
+        let weights = [
+            1.0 - barycentric.x - barycentric.y,
+            barycentric.x,
+            barycentric.y,
+        ];
+
+        let normal = if self.normals.is_empty() {
+            let [a, b, c] = self.triangle(index);
+            (b - a).cross(c - a).normalize()
+        } else {
+            (weights[0] * self.normals[i0]
+                + weights[1] * self.normals[i1]
+                + weights[2] * self.normals[i2])
+                .normalize()
+        };
+
+        let uv = if self.uvs.is_empty() {
+            barycentric
+        } else {
+            weights[0] * self.uvs[i0] + weights[1] * self.uvs[i1] + weights[2] * self.uvs[i2]
+        };
+
+        HitRecord {
+            point,
+            normal,
+            t,
+            uv,
+            barycentric,
+        }
+    }
+
+    fn area_sums(&self) -> &[f32] {
+        self.area_sums.get_or_init(|| {
+            (0..self.triangle_count())
+                .scan(0.0, |sum, index| {
+                    *sum += triangle_area(self.triangle(index));
+                    Some(*sum)
+                })
+                .collect()
+        })
+    }
+}
+
+impl Shape for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.bvh.closest_hit(ray, t_min, t_max, |index, closest| {
+            self.hit_triangle(index, ray, t_min, closest)
+                .map(|record| (record.t, record))
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+
+    fn area(&self) -> f32 {
+        self.area_sums().last().copied().unwrap_or(0.0)
+    }
+
+    fn sample_surface(&self, sample: Vec2) -> Option<HitRecord> {
+        let area_sums = self.area_sums();
+        let area = *area_sums.last()?;
+
+        // Pick a triangle with a probability proportional to its area and reuse the rest of the sample inside it
+        let target = sample.x * area;
+        let index = area_sums
+            .partition_point(|&sum| sum <= target)
+            .min(area_sums.len() - 1);
+        let start = if index == 0 {
+            0.0
+        } else {
+            area_sums[index - 1]
+        };
+        let triangle_area = area_sums[index] - start;
+        let x = ((target - start) / triangle_area).clamp(0.0, 1.0);
+
+        let barycentric = sample_triangle(Vec2::new(x, sample.y));
+        let [a, b, c] = self.triangle(index);
+        let point = a + barycentric.x * (b - a) + barycentric.y * (c - a);
+        Some(self.surface_record(index, barycentric, point, 0.0))
+    }
+
+    fn describe(&self) -> Option<ShapeDescription> {
+        Some(ShapeDescription::Mesh {
+            positions: self.positions.clone(),
+            indices: self.indices.clone(),
+            normals: self.normals.clone(),
+            uvs: self.uvs.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use crate::{ray::Ray, scene::object::Shape};
+
+    use super::TriangleMesh;
+
+    /// Unit square in the xy plane made of two triangles
+    fn quad() -> TriangleMesh {
+        TriangleMesh::new(
+            vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+    }
+
+    #[test]
+    fn hit_closest_triangle() {
+        let ray = Ray {
+            origin: Vec3::new(0.2, 0.7, 1.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        let record = quad().hit(&ray, 0.0, f32::MAX).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-6);
+        assert_eq!(record.normal, Vec3::Z);
+    }
+
+    #[test]
+    fn interpolate_normals_and_uvs() {
+        let normals = vec![Vec3::Z, Vec3::Z, Vec3::X, Vec3::X];
+        let uvs = vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        let mesh = quad().with_normals(normals).with_uvs(uvs);
+
+        let ray = Ray {
+            origin: Vec3::new(0.75, 0.5, 1.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        let record = mesh.hit(&ray, 0.0, f32::MAX).unwrap();
+        assert!((record.uv - Vec2::new(0.75, 0.5)).length() < 1e-5);
+        assert!(record.normal.x > 0.0 && record.normal.z > 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        let _mesh = TriangleMesh::new(vec![Vec3::ZERO], vec![[0, 1, 2]]);
+    }
+}
@@ -1,47 +1,49 @@
-use glam::Vec3;
+use std::sync::Arc;
 
-use crate::{
-    color::Color,
-    ray::{HitRecord, Ray},
-};
+use glam::Vec2;
 
-pub trait Shape {
+use crate::ray::{HitRecord, Ray};
+
+use super::{aabb::Aabb, description::ShapeDescription, material::Material};
+
+pub trait Shape: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+
+    /// Axis aligned box enclosing the whole shape
+    fn bounds(&self) -> Aabb;
+
+    /// Surface area of the shape, zero for shapes that can't be sampled
+    fn area(&self) -> f32 {
+        0.0
+    }
+
+    /// Map a uniform sample in the unit square to a point uniformly distributed over the surface
+    /// Used by area lights, shapes returning None can't be used as one
+    fn sample_surface(&self, _sample: Vec2) -> Option<HitRecord> {
+        None
+    }
+
+    /// Description used to save the shape to a scene file, shapes without one can't be saved
+    fn describe(&self) -> Option<ShapeDescription> {
+        None
+    }
 }
 
 pub struct Object {
     pub shape: Box<dyn Shape>,
-    pub color: Color,
+    pub material: Arc<dyn Material>,
 }
 
 impl Object {
-    pub fn new<S: Shape + 'static>(shape: S, color: Color) -> Self {
-        Self {
-            shape: Box::new(shape),
-            color,
-        }
+    pub fn new<S: Shape + 'static, M: Material + 'static>(shape: S, material: M) -> Self {
+        Self::with_shared_material(shape, Arc::new(material))
     }
 
-    pub fn shade(
-        &self,
-        normal: Vec3,
-        light_in: Color,
-        light_direction: Option<Vec3>,
-        out_direction: Vec3,
-    ) -> Color {
-        if let Some(light_direction) = light_direction {
-            let intensity = normal.dot(light_direction).clamp(0.0, 1.0);
-            let diffuse = intensity * light_in * self.color;
-
-            let half_vector = (light_direction + out_direction).normalize();
-            let n_dot_h = normal.dot(half_vector).clamp(0.0, 1.0);
-            let specular_hardness = 2.0;
-            let intensity = n_dot_h.powf(specular_hardness);
-            let specular = intensity * light_in * self.color;
-
-            diffuse + specular
-        } else {
-            light_in * self.color
+    /// Create an object using a material that can be shared with other objects
+    pub fn with_shared_material<S: Shape + 'static>(shape: S, material: Arc<dyn Material>) -> Self {
+        Self {
+            shape: Box::new(shape),
+            material,
         }
     }
 }
@@ -0,0 +1,133 @@
+use glam::{Vec2, Vec3};
+
+use crate::ray::{HitRecord, Ray};
+
+use super::{aabb::Aabb, description::ShapeDescription, object::Shape};
+
+/// Flat parallelogram spanned by two edges starting at a corner
+/// Its normal is the cross product of the edges, so a counter-clockwise corner, edge_u, edge_v order faces the viewer
+pub struct Rectangle {
+    pub corner: Vec3,
+    pub edge_u: Vec3,
+    pub edge_v: Vec3,
+}
+
+impl Rectangle {
+    pub fn new(corner: Vec3, edge_u: Vec3, edge_v: Vec3) -> Self {
+        Self {
+            corner,
+            edge_u,
+            edge_v,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.edge_u.cross(self.edge_v).normalize()
+    }
+
+    fn surface_record(&self, uv: Vec2, point: Vec3, t: f32) -> HitRecord {
+        HitRecord {
+            point,
+            normal: self.normal(),
+            t,
+            uv,
+            barycentric: Vec2::ZERO,
+        }
+    }
+}
+
+impl Shape for Rectangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let normal = self.edge_u.cross(self.edge_v);
+        let denominator = normal.dot(ray.direction);
+
+        // The ray is parallel to the rectangle
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let t = normal.dot(self.corner - ray.origin) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        // Coordinates of the hit point along both edges, using the dual basis of the edges in the plane
+        let point = ray.at(t);
+        let offset = point - self.corner;
+        let u = offset.cross(self.edge_v).dot(normal) / normal.length_squared();
+        let v = self.edge_u.cross(offset).dot(normal) / normal.length_squared();
+
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        Some(self.surface_record(Vec2::new(u, v), point, t))
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points([
+            self.corner,
+            self.corner + self.edge_u,
+            self.corner + self.edge_v,
+            self.corner + self.edge_u + self.edge_v,
+        ])
+    }
+
+    fn area(&self) -> f32 {
+        self.edge_u.cross(self.edge_v).length()
+    }
+
+    fn sample_surface(&self, sample: Vec2) -> Option<HitRecord> {
+        let point = self.corner + sample.x * self.edge_u + sample.y * self.edge_v;
+        Some(self.surface_record(sample, point, 0.0))
+    }
+
+    fn describe(&self) -> Option<ShapeDescription> {
+        Some(ShapeDescription::Rectangle {
+            corner: self.corner,
+            edge_u: self.edge_u,
+            edge_v: self.edge_v,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use crate::{ray::Ray, scene::object::Shape};
+
+    use super::Rectangle;
+
+    fn rectangle() -> Rectangle {
+        Rectangle::new(
+            Vec3::ZERO,
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        )
+    }
+
+    #[test]
+    fn hit_test() {
+        let ray = Ray {
+            origin: Vec3::new(1.5, 1.0, -0.25),
+            direction: Vec3::NEG_Y,
+        };
+
+        let record = rectangle().hit(&ray, 0.0, f32::MAX).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-6);
+        assert!((record.uv - Vec2::new(0.75, 0.25)).length() < 1e-6);
+        assert_eq!(record.normal, Vec3::Y);
+        assert_eq!(rectangle().area(), 2.0);
+    }
+
+    #[test]
+    fn miss_test() {
+        let ray = Ray {
+            origin: Vec3::new(2.5, 1.0, -0.25),
+            direction: Vec3::NEG_Y,
+        };
+
+        assert!(rectangle().hit(&ray, 0.0, f32::MAX).is_none());
+    }
+}
@@ -1,8 +1,13 @@
-use glam::Vec3;
+use std::f32::consts::{PI, TAU};
 
-use crate::ray::{HitRecord, Ray};
+use glam::{Vec2, Vec3};
 
-use super::object::Shape;
+use crate::{
+    ray::{HitRecord, Ray},
+    sampling::uniform_sample_sphere,
+};
+
+use super::{aabb::Aabb, description::ShapeDescription, object::Shape};
 
 pub struct Sphere {
     pub center: Vec3,
@@ -15,8 +20,24 @@ impl Sphere {
     }
 }
 
+impl Sphere {
+    fn surface_record(&self, point: Vec3, normal: Vec3, t: f32) -> HitRecord {
+        // Longitude and latitude of the point, with the poles on the y axis
+        let u = 0.5 + normal.z.atan2(-normal.x) / TAU;
+        let v = normal.y.clamp(-1.0, 1.0).acos() / PI;
+
+        HitRecord {
+            point,
+            normal,
+            t,
+            uv: Vec2::new(u, v),
+            barycentric: Vec2::ZERO,
+        }
+    }
+}
+
 impl Shape for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<crate::ray::HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
         let oc = ray.origin - self.center;
         let a = ray.direction.length_squared();
         let hb = oc.dot(ray.direction);
@@ -38,11 +59,30 @@ impl Shape for Sphere {
             }
         }
 
-        let t = root;
-        let point = ray.at(t);
-        let normal = (point - self.center) / self.radius;
+        let point = ray.at(root);
+        Some(self.surface_record(point, (point - self.center) / self.radius, root))
+    }
+
+    fn bounds(&self) -> Aabb {
+        let radius = Vec3::splat(self.radius.abs());
+        Aabb::new(self.center - radius, self.center + radius)
+    }
+
+    fn area(&self) -> f32 {
+        2.0 * TAU * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, sample: Vec2) -> Option<HitRecord> {
+        let normal = uniform_sample_sphere(sample);
+        let point = self.center + self.radius.abs() * normal;
+        Some(self.surface_record(point, normal, 0.0))
+    }
 
-        Some(HitRecord { point, normal, t })
+    fn describe(&self) -> Option<ShapeDescription> {
+        Some(ShapeDescription::Sphere {
+            center: self.center,
+            radius: self.radius,
+        })
     }
 }
 
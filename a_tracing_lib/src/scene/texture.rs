@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use glam::Vec2;
+
+use crate::{
+    color::{srgb_to_linear, Color},
+    image::{self, ImageError},
+};
+
+/// Image sampled with texture coordinates, the texels are stored as linear colors
+/// The first row of texels is at v = 0 and the texture repeats outside the range [0, 1]
+#[derive(Debug, Clone)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    texels: Vec<Color>,
+    /// File the image was loaded from and whether it stores sRGB values, needed to save the texture to a scene file
+    source: Option<(PathBuf, bool)>,
+}
+
+impl Texture {
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(texels.len(), (width * height) as usize);
+
+        Self {
+            width,
+            height,
+            texels,
+            source: None,
+        }
+    }
+
+    /// Load a PNG image, color textures are usually stored in sRGB and other data such as roughness linearly
+    pub fn load<P: AsRef<Path>>(path: P, srgb: bool) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        let image = image::read_png(path)?;
+        if image.width == 0 || image.height == 0 {
+            return Err(ImageError::Decode {
+                path: path.to_owned(),
+                message: "the image is empty".to_owned(),
+            });
+        }
+
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            if srgb {
+                srgb_to_linear(value)
+            } else {
+                value
+            }
+        };
+        let texels = image
+            .pixels
+            .iter()
+            .map(|&[r, g, b, _]| Color::new(decode(r), decode(g), decode(b)))
+            .collect();
+
+        Ok(Self::new(image.width, image.height, texels).with_source(path, srgb))
+    }
+
+    /// Remember the file the texels were read from, so the texture can be saved to a scene file
+    pub fn with_source<P: AsRef<Path>>(mut self, path: P, srgb: bool) -> Self {
+        self.source = Some((path.as_ref().to_owned(), srgb));
+        self
+    }
+
+    /// File the image was loaded from and whether it stores sRGB values
+    pub fn source(&self) -> Option<(&Path, bool)> {
+        self.source
+            .as_ref()
+            .map(|(path, srgb)| (path.as_path(), *srgb))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// All texels in row-major order
+    pub fn texels(&self) -> &[Color] {
+        &self.texels
+    }
+
+    pub fn texel(&self, x: u32, y: u32) -> Color {
+        self.texels[(y * self.width + x) as usize]
+    }
+
+    /// Bilinearly filtered color at the given texture coordinates
+    pub fn sample(&self, uv: Vec2) -> Color {
+        let x = uv.x.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = uv.y.rem_euclid(1.0) * self.height as f32 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+
+        let wrap = |value: f32, size: u32| (value as i64).rem_euclid(size as i64) as u32;
+        let (x0, x1) = (wrap(x0, self.width), wrap(x0 + 1.0, self.width));
+        let (y0, y1) = (wrap(y0, self.height), wrap(y0 + 1.0, self.height));
+
+        let top = self.texel(x0, y0).lerp(self.texel(x1, y0), tx);
+        let bottom = self.texel(x0, y1).lerp(self.texel(x1, y1), tx);
+        top.lerp(bottom, ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use glam::Vec2;
+
+    use crate::{
+        color::{self, Color},
+        image::write_png,
+        test_util::temp_path,
+    };
+
+    use super::Texture;
+
+    #[test]
+    fn load_decodes_srgb() {
+        let path = temp_path("texture.png");
+        write_png(&path, 2, 1, &[[255, 128, 0, 255], [0, 0, 0, 255]]).unwrap();
+        let srgb = Texture::load(&path, true).unwrap();
+        let linear = Texture::load(&path, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((srgb.width(), srgb.height()), (2, 1));
+        assert!((srgb.texel(0, 0).g - 0.2158).abs() < 1e-3);
+        assert!((linear.texel(0, 0).g - 0.502).abs() < 1e-3);
+        assert_eq!(srgb.source(), Some((path.as_path(), true)));
+    }
+
+    #[test]
+    fn sample_texel_centers() {
+        let texture = Texture::new(2, 1, vec![color::BLACK, color::WHITE]);
+
+        assert_eq!(texture.sample(Vec2::new(0.25, 0.5)), color::BLACK);
+        assert_eq!(texture.sample(Vec2::new(0.75, 0.5)), color::WHITE);
+    }
+
+    #[test]
+    fn sample_interpolates_and_wraps() {
+        let texture = Texture::new(2, 1, vec![color::BLACK, color::WHITE]);
+
+        assert_eq!(
+            texture.sample(Vec2::new(0.5, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(texture.sample(Vec2::new(1.25, -0.5)), color::BLACK);
+    }
+}
@@ -0,0 +1,154 @@
+use glam::{Vec2, Vec3};
+
+use crate::ray::{HitRecord, Ray};
+
+use super::{aabb::Aabb, description::ShapeDescription, object::Shape};
+
+/// Single flat triangle, its normal follows the counter-clockwise winding of the vertices
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        Self {
+            vertices: [a, b, c],
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (b - a).cross(c - a).normalize()
+    }
+}
+
+/// Möller–Trumbore ray-triangle intersection, both sides of the triangle are hit
+/// Returns the ray parameter and the barycentric coordinates of the second and third vertex
+pub(crate) fn intersect_triangle(
+    ray: &Ray,
+    vertices: [Vec3; 3],
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, Vec2)> {
+    let [a, b, c] = vertices;
+    let edge1 = b - a;
+    let edge2 = c - a;
+
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+
+    // The ray is parallel to the triangle
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let offset = ray.origin - a;
+
+    let u = offset.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = offset.cross(edge1);
+    let v = ray.direction.dot(q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse_determinant;
+    if t < t_min || t_max < t {
+        return None;
+    }
+
+    Some((t, Vec2::new(u, v)))
+}
+
+/// Map a uniform sample in the unit square to uniformly distributed barycentric coordinates of the second and third vertex
+pub(crate) fn sample_triangle(sample: Vec2) -> Vec2 {
+    let root = sample.x.sqrt();
+    Vec2::new(root * (1.0 - sample.y), root * sample.y)
+}
+
+pub(crate) fn triangle_area(vertices: [Vec3; 3]) -> f32 {
+    let [a, b, c] = vertices;
+    0.5 * (b - a).cross(c - a).length()
+}
+
+impl Shape for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t, barycentric) = intersect_triangle(ray, self.vertices, t_min, t_max)?;
+
+        Some(HitRecord {
+            point: ray.at(t),
+            normal: self.normal(),
+            t,
+            uv: barycentric,
+            barycentric,
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(self.vertices)
+    }
+
+    fn area(&self) -> f32 {
+        triangle_area(self.vertices)
+    }
+
+    fn sample_surface(&self, sample: Vec2) -> Option<HitRecord> {
+        let [a, b, c] = self.vertices;
+        let barycentric = sample_triangle(sample);
+
+        Some(HitRecord {
+            point: a + barycentric.x * (b - a) + barycentric.y * (c - a),
+            normal: self.normal(),
+            t: 0.0,
+            uv: barycentric,
+            barycentric,
+        })
+    }
+
+    fn describe(&self) -> Option<ShapeDescription> {
+        Some(ShapeDescription::Triangle {
+            vertices: self.vertices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::{ray::Ray, scene::object::Shape};
+
+    use super::Triangle;
+
+    fn triangle() -> Triangle {
+        Triangle::new(Vec3::ZERO, Vec3::X, Vec3::Y)
+    }
+
+    #[test]
+    fn hit_test() {
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, 1.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        let record = triangle().hit(&ray, 0.0, f32::MAX).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-6);
+        assert!((record.barycentric.x - 0.25).abs() < 1e-6);
+        assert!((record.barycentric.y - 0.25).abs() < 1e-6);
+        assert_eq!(record.normal, Vec3::Z);
+    }
+
+    #[test]
+    fn miss_test() {
+        let ray = Ray {
+            origin: Vec3::new(0.75, 0.75, 1.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        assert!(triangle().hit(&ray, 0.0, f32::MAX).is_none());
+    }
+}
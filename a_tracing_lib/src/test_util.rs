@@ -0,0 +1,6 @@
+use std::{env, path::PathBuf, process};
+
+/// Path in the temporary directory that is unique to the test process, the name keeps tests running in parallel apart
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("a_tracing_{}_{name}", process::id()))
+}
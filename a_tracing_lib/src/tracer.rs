@@ -1,14 +1,43 @@
+pub mod aov;
+pub mod cancel;
+mod render_job;
+pub mod tile;
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    color::{Color, BLACK},
-    sampling::{RegularSampler, SampleGenerator},
-    scene::Scene,
+    color::{display::DisplayTransform, Color},
+    image::{self, ImageChannel, ImageError, ImageFormat},
+    integrator::{DebugIntegrator, DebugMode, Integrator, PathTracer},
+    sampling::{RegularSampler, SampleGenerator, SampleStream},
+    scene::{description::SceneFileError, Scene},
 };
 
+use self::{
+    aov::{Aov, AovBuffer},
+    cancel::CancelToken,
+    render_job::{RenderJob, RenderedTile},
+};
+
+/// Integrator of the quick render, shows the unlit surface colors
+const QUICK_RENDER_INTEGRATOR: DebugIntegrator = DebugIntegrator::new(DebugMode::Albedo);
+
 pub struct ATracer {
     render_settings: RenderSettings,
     color_buffer: Vec<Color>,
+    accumulation_buffer: Vec<Color>,
+    sample_count_buffer: Vec<u32>,
+    /// Extra per-pixel buffers saved as additional channels of OpenEXR images
+    aov_buffers: Vec<AovBuffer>,
+    /// AOV shown by ```draw``` instead of the color buffer
+    displayed_aov: Option<Aov>,
+    /// Exposure and tone mapping applied to the color buffer by ```draw```
+    display_transform: DisplayTransform,
     render_status: RenderStatus,
-    scene: Scene,
+    scene: Arc<Scene>,
+    render_job: Option<RenderJob>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -23,84 +52,246 @@ impl ATracer {
     pub fn new(width: u32, height: u32) -> Self {
         let render_settings = RenderSettings {
             resolution: Resolution { width, height },
-            number_of_samples: 9,
-            sampler: Box::new(RegularSampler::new(3)),
+            ..Default::default()
         };
 
+        let pixel_count = (width * height) as usize;
+
         ATracer {
             render_settings,
-            color_buffer: vec![Default::default(); (width * height) as usize],
+            color_buffer: vec![Default::default(); pixel_count],
+            accumulation_buffer: vec![Default::default(); pixel_count],
+            sample_count_buffer: vec![0; pixel_count],
+            aov_buffers: Vec::new(),
+            displayed_aov: None,
+            display_transform: DisplayTransform::default(),
             render_status: RenderStatus::NeedsQuickrender,
-            scene: Scene::default(),
+            scene: Arc::new(Scene::default()),
+            render_job: None,
         }
     }
 
+    pub fn render_status(&self) -> RenderStatus {
+        self.render_status
+    }
+
+    pub fn render_settings(&self) -> &RenderSettings {
+        &self.render_settings
+    }
+
+    /// Get mutable access to the render settings, a running render is canceled
+    /// The resolution should be changed using ```resize``` instead so the buffers stay in sync
+    pub fn render_settings_mut(&mut self) -> &mut RenderSettings {
+        self.cancel_render();
+        &mut self.render_settings
+    }
+
+    pub fn get_scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Get mutable access to the scene
+    /// A running render is canceled since it no longer matches the edited scene
     pub fn get_scene_mut(&mut self) -> &mut Scene {
+        self.cancel_render();
         self.render_status = RenderStatus::NeedsQuickrender;
-        &mut self.scene
+        Arc::get_mut(&mut self.scene).expect("Scene is still shared with a render job.")
+    }
+
+    /// Replace the scene and render settings with the contents of a scene file
+    /// The buffers are resized to the resolution of the loaded settings
+    pub fn load_scene<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SceneFileError> {
+        let (scene, render_settings) = Scene::load(path)?;
+        let resolution = render_settings.resolution;
+
+        self.cancel_render();
+        self.scene = Arc::new(scene);
+        self.render_settings = render_settings;
+        self.resize(resolution.width, resolution.height);
+
+        Ok(())
+    }
+
+    /// Save the scene and render settings to a scene file
+    pub fn save_scene<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneFileError> {
+        self.scene.save(path, &self.render_settings)
     }
 
     pub fn update(&mut self) {
         match self.render_status {
             RenderStatus::NeedsQuickrender => self.quick_render(),
             RenderStatus::Ready => {}
-            RenderStatus::Rendering => {}
+            RenderStatus::Rendering => self.poll_render_job(),
             RenderStatus::Finished => {}
         }
     }
 
     /// Resize and clear all buffers of the tracer
+    /// A running render is canceled
     pub fn resize(&mut self, width: u32, height: u32) {
+        self.cancel_render();
         self.render_settings.resolution.width = width;
         self.render_settings.resolution.height = height;
-        self.color_buffer = vec![Default::default(); (width * height) as usize];
+        let pixel_count = (width * height) as usize;
+        self.color_buffer = vec![Default::default(); pixel_count];
+        self.accumulation_buffer = vec![Default::default(); pixel_count];
+        self.sample_count_buffer = vec![0; pixel_count];
+        for buffer in &mut self.aov_buffers {
+            buffer.reset(pixel_count);
+        }
         self.get_scene_mut()
             .camera
             .set_aspect_ratio(width as f32 / height as f32);
     }
 
     /// Start rendering the current scene with the current settings to the color buffer
+    /// The viewport is split into tiles which are rendered on a pool of worker threads,
+    /// every finished tile is copied to the color buffer by the next ```update``` call
+    /// In progressive mode the render keeps refining the image until a limit is hit or ```stop_render``` is called
     pub fn start_render(&mut self) {
         if self.render_status != RenderStatus::Ready {
             return;
         }
 
-        let width = self.render_settings.resolution.width;
-        let height = self.render_settings.resolution.height;
+        self.accumulation_buffer.fill(Default::default());
+        self.sample_count_buffer.fill(0);
 
-        for i in 0..width {
-            for j in 0..height {
-                let mut color = BLACK;
-                let nb_samples = self.render_settings.number_of_samples;
+        // Every AOV gets a single buffer, the render job sends the AOVs in the same order
+        let mut enabled = Vec::new();
+        for &aov in &self.render_settings.aovs {
+            if !enabled.contains(&aov) {
+                enabled.push(aov);
+            }
+        }
+        self.render_settings.aovs = enabled;
 
-                for s in 0..nb_samples {
-                    let sample = self.render_settings.sampler.get_sample(s);
+        let pixel_count = self.color_buffer.len();
+        self.aov_buffers = self
+            .render_settings
+            .aovs
+            .iter()
+            .map(|&aov| AovBuffer::new(aov, pixel_count))
+            .collect();
 
-                    let h = (i as f32 + sample.x) / (width - 1) as f32;
-                    let v = 1.0 - ((j as f32 + sample.y) / (height - 1) as f32);
+        self.render_job = Some(RenderJob::start(
+            Arc::clone(&self.scene),
+            self.render_settings.clone(),
+        ));
+        self.render_status = RenderStatus::Rendering;
+    }
 
-                    let sample_color = self.render_pixel(h, v);
+    /// Copy all tiles that were finished since the last call to the color buffer
+    fn poll_render_job(&mut self) {
+        let Some(job) = &mut self.render_job else {
+            return;
+        };
 
-                    color += sample_color;
-                }
+        if job.cancel_token().is_canceled() {
+            self.cancel_render();
+            return;
+        }
 
-                color /= nb_samples as f32;
+        // Check before taking the tiles so no tile sent right before the workers stop is missed
+        let finished = job.is_finished();
 
-                let index = (j * width + i) as usize;
-                self.color_buffer[index] = color;
+        for tile in job.finished_tiles() {
+            self.write_tile(tile);
+        }
+
+        if finished {
+            self.wait_for_render_job();
+            self.render_status = RenderStatus::Finished;
+        }
+    }
+
+    /// Fraction of the running render that has been copied to the color buffer
+    /// Unbounded progressive renders always report 0, see ```completed_passes``` instead
+    pub fn render_progress(&self) -> f32 {
+        match (&self.render_job, self.render_status) {
+            (Some(job), RenderStatus::Rendering) => job.progress(),
+            (_, RenderStatus::Finished) => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Number of samples per pixel that have been copied to the color buffer by a running progressive render
+    pub fn completed_passes(&self) -> u32 {
+        self.render_job
+            .as_ref()
+            .map_or(0, |job| job.completed_passes())
+    }
+
+    /// Block until the workers of the running render job have stopped and drop the job
+    fn wait_for_render_job(&mut self) {
+        if let Some(job) = self.render_job.take() {
+            job.join();
+        }
+    }
+
+    /// Stop the running render, if any, and return to needing a quick render
+    /// Blocks only until every worker has noticed the cancellation, which happens within a single pixel
+    pub fn cancel_render(&mut self) {
+        if let Some(job) = self.render_job.take() {
+            job.cancel();
+            self.render_status = RenderStatus::NeedsQuickrender;
+        }
+    }
+
+    /// Stop the running render, if any, but keep everything that was rendered so far as the finished image
+    /// This is the way to end an unbounded progressive render
+    pub fn stop_render(&mut self) {
+        if let Some(job) = self.render_job.take() {
+            for tile in job.stop() {
+                self.write_tile(tile);
             }
+
+            self.render_status = RenderStatus::Finished;
         }
+    }
 
-        self.render_status = RenderStatus::Finished;
+    /// Token that cancels the running render when triggered, for use from other threads
+    /// The tracer notices the cancellation on the next ```update``` call
+    pub fn cancel_token(&self) -> Option<CancelToken> {
+        self.render_job
+            .as_ref()
+            .map(|job| job.cancel_token().clone())
     }
 
-    fn render_pixel(&self, h: f32, v: f32) -> Color {
-        let scene = &self.scene;
-        let ray = scene.camera.get_ray(h, v);
-        scene.trace_ray(&ray)
+    fn write_tile(&mut self, rendered: RenderedTile) {
+        let width = self.render_settings.resolution.width;
+        let aov_channels = rendered.aovs.len() / rendered.colors.len().max(1);
+
+        for (tile_index, ((i, j), color)) in rendered.tile.pixels().zip(rendered.colors).enumerate()
+        {
+            let index = (j * width + i) as usize;
+            let previous_count = self.sample_count_buffer[index];
+            self.accumulation_buffer[index] += color;
+            self.sample_count_buffer[index] += rendered.sample_count;
+            let sample_count = self.sample_count_buffer[index];
+            self.color_buffer[index] = self.accumulation_buffer[index] / sample_count as f32;
+
+            // The AOV sums are sent in the order of the buffers, skipping the AOVs that aren't traced
+            let mut samples =
+                &rendered.aovs[tile_index * aov_channels..(tile_index + 1) * aov_channels];
+            for buffer in &mut self.aov_buffers {
+                let channels = if buffer.aov().is_traced() {
+                    buffer.channel_count()
+                } else {
+                    0
+                };
+
+                buffer.accumulate(index, &samples[..channels], previous_count, sample_count);
+                samples = &samples[channels..];
+            }
+        }
     }
 
     pub fn quick_render(&mut self) {
+        self.cancel_render();
+
+        // The AOVs of the previous render no longer match, so the quick render is shown instead
+        self.aov_buffers.clear();
+
         let width = self.render_settings.resolution.width;
         let height = self.render_settings.resolution.height;
 
@@ -122,7 +313,9 @@ impl ATracer {
     fn quick_render_pixel(&self, h: f32, v: f32) -> Color {
         let scene = &self.scene;
         let ray = scene.camera.get_ray(h, v);
-        scene.first_hit_color(&ray)
+        // The quick render integrator makes no random decisions
+        let mut sampler = SampleStream::new(self.render_settings.sampler.as_ref(), 0, 0);
+        QUICK_RENDER_INTEGRATOR.radiance(scene, &ray, 1, &mut sampler)
     }
 
     /// Draw the current color buffer of the tracer to the supplied frame
@@ -132,18 +325,160 @@ impl ATracer {
             "Size of color buffer and supplied frame didn't match in draw call."
         );
 
-        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            pixel.copy_from_slice(&(<[u8; 4]>::from(self.color_buffer[i])))
+        for (pixel, color) in frame.chunks_exact_mut(4).zip(self.display_colors()) {
+            pixel.copy_from_slice(&color)
+        }
+    }
+
+    /// The AOV shown by ```draw``` and saved to PNG and PPM images instead of the color buffer, if any
+    pub fn displayed_aov(&self) -> Option<Aov> {
+        self.displayed_aov
+    }
+
+    /// Show an AOV instead of the color buffer, AOVs that aren't enabled in the render settings show the color buffer
+    pub fn set_displayed_aov(&mut self, aov: Option<Aov>) {
+        self.displayed_aov = aov;
+    }
+
+    pub fn display_transform(&self) -> DisplayTransform {
+        self.display_transform
+    }
+
+    /// Change the exposure and tone mapping, only the displayed colors change so nothing has to be rendered again
+    pub fn set_display_transform(&mut self, display_transform: DisplayTransform) {
+        self.display_transform = display_transform;
+    }
+
+    /// The color buffer passed through the display transform, or the displayed AOV, as the 8-bit RGBA colors shown by ```draw```
+    fn display_colors(&self) -> Box<dyn Iterator<Item = [u8; 4]> + '_> {
+        let aov_buffer = self
+            .aov_buffers
+            .iter()
+            .find(|buffer| Some(buffer.aov()) == self.displayed_aov);
+
+        match aov_buffer {
+            Some(buffer) => {
+                let max_value = buffer.max_finite_value();
+                Box::new(
+                    (0..self.color_buffer.len())
+                        .map(move |index| buffer.display_color(index, max_value).into()),
+                )
+            }
+            None => Box::new(
+                self.color_buffer
+                    .iter()
+                    .map(|&color| self.display_transform.to_srgb8(color)),
+            ),
         }
     }
+
+    /// The extra per-pixel buffers next to the color buffer
+    pub fn aov_buffers(&self) -> &[AovBuffer] {
+        &self.aov_buffers
+    }
+
+    /// Save the current color buffer to an image file
+    /// PNG and PPM store the sRGB colors as shown by ```draw```, HDR stores the linear colors
+    /// EXR stores the linear colors in the R, G and B channels and every AOV buffer in channels named like depth.Z
+    /// Without a format the format is chosen by the extension of the path
+    pub fn save_image<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: Option<ImageFormat>,
+    ) -> Result<(), ImageError> {
+        let path = path.as_ref();
+        let format = format
+            .or_else(|| ImageFormat::from_path(path))
+            .ok_or_else(|| ImageError::UnknownFormat {
+                path: path.to_owned(),
+            })?;
+        let Resolution { width, height } = self.render_settings.resolution;
+
+        match format {
+            ImageFormat::Png => {
+                let pixels: Vec<_> = self.display_colors().collect();
+                image::write_png(path, width, height, &pixels)
+            }
+            ImageFormat::Ppm => {
+                let pixels: Vec<_> = self.display_colors().collect();
+                image::write_ppm(path, width, height, &pixels)
+            }
+            ImageFormat::Hdr => image::write_hdr(path, width, height, &self.color_buffer),
+            ImageFormat::Exr => image::write_exr(path, width, height, self.image_channels()),
+        }
+    }
+
+    /// The color buffer and all AOV buffers split into their channels
+    fn image_channels(&self) -> Vec<ImageChannel> {
+        let color_channel = |name: &str, value: fn(&Color) -> f32| ImageChannel {
+            name: name.to_owned(),
+            values: self.color_buffer.iter().map(value).collect(),
+        };
+
+        let mut channels = vec![
+            color_channel("R", |color| color.r),
+            color_channel("G", |color| color.g),
+            color_channel("B", |color| color.b),
+        ];
+
+        for buffer in &self.aov_buffers {
+            for (index, channel_name) in buffer.channel_names().iter().enumerate() {
+                channels.push(ImageChannel {
+                    name: format!("{}.{channel_name}", buffer.name()),
+                    values: buffer.channel(index).collect(),
+                });
+            }
+        }
+
+        channels
+    }
 }
 
+#[derive(Clone)]
 pub struct RenderSettings {
     pub resolution: Resolution,
+    /// Samples per pixel of a full render, unused by progressive renders
     pub number_of_samples: u32,
-    pub sampler: Box<dyn SampleGenerator>,
+    pub sampler: Arc<dyn SampleGenerator>,
+    pub render_mode: RenderMode,
+    /// Maximum number of surfaces a traced path can hit, 1 only gives direct lighting
+    pub max_depth: u32,
+    /// Rendering algorithm used by full renders
+    pub integrator: Arc<dyn Integrator>,
+    /// Extra per-pixel outputs rendered into their own buffers next to the color buffer
+    pub aovs: Vec<Aov>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            resolution: Resolution {
+                width: 1280,
+                height: 720,
+            },
+            number_of_samples: 9,
+            sampler: Arc::new(RegularSampler::new(3)),
+            render_mode: RenderMode::Full,
+            max_depth: 5,
+            integrator: Arc::new(PathTracer),
+            aovs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// Render every pixel with the configured number of samples in a single pass
+    Full,
+    /// Render one sample per pixel per pass and accumulate the passes until stopped
+    /// The render also finishes once either of the optional limits is hit
+    Progressive {
+        max_samples: Option<u32>,
+        time_budget: Option<Duration>,
+    },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Resolution {
     pub width: u32,
     pub height: u32,
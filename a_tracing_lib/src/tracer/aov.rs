@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::{self, Color},
+    ray::Ray,
+    scene::Scene,
+};
+
+/// Arbitrary output variables that can be rendered next to the color buffer
+/// They are enabled through the ```aovs``` of the render settings, for debugging or as denoiser input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Aov {
+    /// Distance from the camera to the first hit, infinite where nothing was hit
+    /// Taken from the first sample of every pixel so a pixel on a silhouette doesn't average a hit with infinity
+    Depth,
+    /// World space normal of the first hit
+    Normal,
+    /// Unlit surface color of the first hit, the background color where nothing was hit
+    Albedo,
+    /// World space position of the first hit
+    Position,
+    /// Index of the first hit object in the scene, -1 where nothing was hit
+    /// Taken from the first sample of every pixel since ids can't be averaged
+    ObjectId,
+    /// Number of samples accumulated in every pixel
+    SampleCount,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::Position,
+        Aov::ObjectId,
+        Aov::SampleCount,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::SampleCount => "sample_count",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|aov| aov.name() == name)
+    }
+
+    pub fn channel_names(self) -> &'static [&'static str] {
+        match self {
+            Aov::Depth => &["Z"],
+            Aov::Normal | Aov::Position => &["X", "Y", "Z"],
+            Aov::Albedo => &["R", "G", "B"],
+            Aov::ObjectId => &["id"],
+            Aov::SampleCount => &["count"],
+        }
+    }
+
+    /// Returns true if the values of the samples are averaged, otherwise the first sample is kept
+    pub(crate) fn is_averaged(self) -> bool {
+        !matches!(self, Aov::Depth | Aov::ObjectId)
+    }
+
+    /// Returns true if the render workers compute the AOV, the sample count is known by the tracer itself
+    pub(crate) fn is_traced(self) -> bool {
+        !matches!(self, Aov::SampleCount)
+    }
+}
+
+/// Add the values of the traced AOVs for a single camera ray to the sums, in the order of the given AOVs
+/// first_sample tells whether the ray is the first sample of the pixel in this tile, only that one sets the non-averaged AOVs
+pub(crate) fn trace_aovs(
+    scene: &Scene,
+    ray: &Ray,
+    aovs: &[Aov],
+    first_sample: bool,
+    sums: &mut [f32],
+) {
+    let hit = scene.first_hit_index(ray);
+    let mut offset = 0;
+
+    for &aov in aovs.iter().filter(|aov| aov.is_traced()) {
+        let values: [f32; 3] = match (aov, &hit) {
+            (Aov::Depth, Some((_, record))) => [record.t * ray.direction.length(), 0.0, 0.0],
+            (Aov::Depth, None) => [f32::INFINITY, 0.0, 0.0],
+            (Aov::Normal, Some((_, record))) => record.normal.to_array(),
+            (Aov::Position, Some((_, record))) => record.point.to_array(),
+            (Aov::Normal | Aov::Position, None) => [0.0; 3],
+            (Aov::Albedo, Some((index, record))) => {
+                let albedo = scene.objects()[*index].material.albedo(record);
+                albedo.into()
+            }
+            (Aov::Albedo, None) => scene.background_color(&ray.direction).into(),
+            (Aov::ObjectId, Some((index, _))) => [*index as f32, 0.0, 0.0],
+            (Aov::ObjectId, None) => [-1.0, 0.0, 0.0],
+            (Aov::SampleCount, _) => unreachable!("The sample count isn't traced."),
+        };
+
+        let channel_count = aov.channel_names().len();
+        let target = &mut sums[offset..offset + channel_count];
+
+        if aov.is_averaged() {
+            for (sum, value) in target.iter_mut().zip(values) {
+                *sum += value;
+            }
+        } else if first_sample {
+            target.copy_from_slice(&values[..channel_count]);
+        }
+
+        offset += channel_count;
+    }
+}
+
+/// Number of floats per pixel the render workers send for the given AOVs
+pub(crate) fn traced_channel_count(aovs: &[Aov]) -> usize {
+    aovs.iter()
+        .filter(|aov| aov.is_traced())
+        .map(|aov| aov.channel_names().len())
+        .sum()
+}
+
+/// Per-pixel buffer of an arbitrary output variable, rendered next to the color buffer
+/// Every pixel stores one float per named channel, the values are kept unclamped
+#[derive(Debug, Clone)]
+pub struct AovBuffer {
+    aov: Aov,
+    /// Channel values interleaved per pixel in row-major order
+    values: Vec<f32>,
+    /// Sums of the samples of the averaged channels, interleaved like the values
+    sums: Vec<f32>,
+}
+
+impl AovBuffer {
+    pub fn new(aov: Aov, pixel_count: usize) -> Self {
+        let channel_count = aov.channel_names().len();
+
+        Self {
+            aov,
+            values: vec![0.0; pixel_count * channel_count],
+            sums: vec![0.0; pixel_count * channel_count],
+        }
+    }
+
+    pub fn aov(&self) -> Aov {
+        self.aov
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.aov.name()
+    }
+
+    pub fn channel_names(&self) -> &'static [&'static str] {
+        self.aov.channel_names()
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channel_names().len()
+    }
+
+    /// All values interleaved per pixel
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// The values of every channel of the pixel at the given index
+    pub fn pixel(&self, index: usize) -> &[f32] {
+        let channel_count = self.channel_count();
+        &self.values[index * channel_count..(index + 1) * channel_count]
+    }
+
+    /// The values of a single channel for every pixel
+    pub fn channel(&self, channel: usize) -> impl Iterator<Item = f32> + '_ {
+        self.values
+            .iter()
+            .skip(channel)
+            .step_by(self.channel_count())
+            .copied()
+    }
+
+    /// Resize to the given number of pixels and reset every value to zero
+    pub fn reset(&mut self, pixel_count: usize) {
+        self.values = vec![0.0; pixel_count * self.channel_count()];
+        self.sums = vec![0.0; pixel_count * self.channel_count()];
+    }
+
+    /// Add the samples of a rendered pixel
+    /// previous_count is the number of samples the pixel had before, sample_count the number it has now
+    pub(crate) fn accumulate(
+        &mut self,
+        index: usize,
+        samples: &[f32],
+        previous_count: u32,
+        sample_count: u32,
+    ) {
+        let channel_count = self.channel_count();
+        let range = index * channel_count..(index + 1) * channel_count;
+
+        match self.aov {
+            Aov::SampleCount => self.values[range].fill(sample_count as f32),
+            aov if aov.is_averaged() => {
+                let sums = &mut self.sums[range.clone()];
+                for (sum, sample) in sums.iter_mut().zip(samples) {
+                    *sum += sample;
+                }
+
+                for (value, sum) in self.values[range].iter_mut().zip(sums.iter()) {
+                    *value = sum / sample_count as f32;
+                }
+            }
+            _ => {
+                if previous_count == 0 {
+                    self.values[range].copy_from_slice(samples);
+                }
+            }
+        }
+    }
+
+    /// Color used to show the pixel at the given index on screen
+    /// max_value is the largest finite value of the buffer, used to scale depth and sample count
+    pub(crate) fn display_color(&self, index: usize, max_value: f32) -> Color {
+        let pixel = self.pixel(index);
+
+        match self.aov {
+            Aov::Depth if pixel[0].is_finite() && max_value > 0.0 => {
+                let brightness = 1.0 - pixel[0] / max_value;
+                Color::new(brightness, brightness, brightness)
+            }
+            Aov::Depth => color::BLACK,
+            Aov::Normal => Color::new(
+                pixel[0] * 0.5 + 0.5,
+                pixel[1] * 0.5 + 0.5,
+                pixel[2] * 0.5 + 0.5,
+            ),
+            Aov::Albedo => Color::new(pixel[0], pixel[1], pixel[2]),
+            Aov::Position => Color::new(
+                pixel[0].rem_euclid(1.0),
+                pixel[1].rem_euclid(1.0),
+                pixel[2].rem_euclid(1.0),
+            ),
+            Aov::ObjectId if pixel[0] < 0.0 => color::BLACK,
+            Aov::ObjectId => id_color(pixel[0] as u32),
+            Aov::SampleCount if max_value > 0.0 => {
+                let brightness = pixel[0] / max_value;
+                Color::new(brightness, brightness, brightness)
+            }
+            Aov::SampleCount => color::BLACK,
+        }
+    }
+
+    /// Largest finite value of all channels
+    pub(crate) fn max_finite_value(&self) -> f32 {
+        self.values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// A distinct bright color for every object id
+fn id_color(id: u32) -> Color {
+    let hash = id.wrapping_add(1).wrapping_mul(0x9e37_79b9);
+    let channel = |shift: u32| 0.3 + 0.7 * ((hash >> shift) & 0xff) as f32 / 255.0;
+    Color::new(channel(0), channel(8), channel(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aov, AovBuffer};
+
+    #[test]
+    fn channels_are_interleaved() {
+        let mut buffer = AovBuffer::new(Aov::Normal, 2);
+        buffer.accumulate(1, &[1.0, 2.0, 3.0], 0, 1);
+
+        assert_eq!(buffer.pixel(0), &[0.0, 0.0, 0.0]);
+        assert_eq!(buffer.channel(1).collect::<Vec<_>>(), vec![0.0, 2.0]);
+        assert_eq!(buffer.values().len(), 6);
+    }
+
+    #[test]
+    fn accumulate_averages_and_keeps_ids() {
+        let mut positions = AovBuffer::new(Aov::Position, 1);
+        positions.accumulate(0, &[2.0, 0.0, 1.0], 0, 1);
+        positions.accumulate(0, &[6.0, 0.0, 2.0], 1, 3);
+        assert_eq!(positions.pixel(0), &[8.0 / 3.0, 0.0, 1.0]);
+
+        let mut depth = AovBuffer::new(Aov::Depth, 1);
+        depth.accumulate(0, &[2.0], 0, 1);
+        depth.accumulate(0, &[f32::INFINITY], 1, 2);
+        assert_eq!(depth.pixel(0), &[2.0]);
+
+        let mut ids = AovBuffer::new(Aov::ObjectId, 1);
+        ids.accumulate(0, &[4.0], 0, 1);
+        ids.accumulate(0, &[7.0], 1, 2);
+        assert_eq!(ids.pixel(0), &[4.0]);
+
+        let mut counts = AovBuffer::new(Aov::SampleCount, 1);
+        counts.accumulate(0, &[], 4, 9);
+        assert_eq!(counts.pixel(0), &[9.0]);
+    }
+}
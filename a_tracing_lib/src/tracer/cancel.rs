@@ -0,0 +1,41 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Shared flag used to ask the workers of a running render to stop
+/// Cloning the token gives another handle to the same flag, so a render can be canceled from any thread
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    canceled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the render using this token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancelToken;
+
+    #[test]
+    fn test_cancel_shared_between_clones() {
+        let token = CancelToken::new();
+        let handle = token.clone();
+        assert!(!token.is_canceled());
+
+        handle.cancel();
+        assert!(token.is_canceled());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use crate::{
+    color::{Color, BLACK},
+    sampling::SampleStream,
+    scene::Scene,
+};
+
+use super::{
+    aov::{trace_aovs, traced_channel_count},
+    cancel::CancelToken,
+    tile::{split_into_tiles, Tile, TILE_SIZE},
+    RenderMode, RenderSettings,
+};
+
+/// The rendered colors of a single tile in row-major order
+/// The colors are the sum of sample_count samples per pixel so tiles of multiple passes can be accumulated
+pub(crate) struct RenderedTile {
+    pub tile: Tile,
+    pub colors: Vec<Color>,
+    /// Sums of the traced AOVs of the render settings, interleaved per pixel
+    pub aovs: Vec<f32>,
+    pub sample_count: u32,
+}
+
+/// A render running on a pool of worker threads
+/// The workers take tiles from a shared queue and send every finished tile back over a channel
+/// In progressive mode the queue restarts at the first tile with the next sample after every pass
+pub(crate) struct RenderJob {
+    workers: Vec<JoinHandle<()>>,
+    cancel_token: CancelToken,
+    receiver: Receiver<RenderedTile>,
+    mode: RenderMode,
+    started: Instant,
+    total_tiles: usize,
+    received_tiles: usize,
+}
+
+/// The work shared between all worker threads of a render job
+struct TileQueue {
+    scene: Arc<Scene>,
+    settings: RenderSettings,
+    tiles: Vec<Tile>,
+    next_tile: AtomicUsize,
+    cancel_token: CancelToken,
+    started: Instant,
+}
+
+impl RenderJob {
+    /// Split the viewport into tiles and start rendering them on one worker per available core
+    pub fn start(scene: Arc<Scene>, settings: RenderSettings) -> Self {
+        let resolution = settings.resolution;
+        let tiles = split_into_tiles(resolution.width, resolution.height, TILE_SIZE);
+        let total_tiles = tiles.len();
+        let thread_count = thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .min(total_tiles.max(1));
+
+        let cancel_token = CancelToken::new();
+        let mode = settings.render_mode;
+        let started = Instant::now();
+
+        let queue = Arc::new(TileQueue {
+            scene,
+            settings,
+            tiles,
+            next_tile: AtomicUsize::new(0),
+            cancel_token: cancel_token.clone(),
+            started,
+        });
+
+        let (sender, receiver) = mpsc::channel();
+
+        let workers = (0..thread_count)
+            .map(|index| {
+                let queue = Arc::clone(&queue);
+                let sender = sender.clone();
+
+                thread::Builder::new()
+                    .name(format!("render worker {index}"))
+                    .spawn(move || queue.work(sender))
+                    .expect("Spawning render worker failed.")
+            })
+            .collect();
+
+        Self {
+            workers,
+            cancel_token,
+            receiver,
+            mode,
+            started,
+            total_tiles,
+            received_tiles: 0,
+        }
+    }
+
+    /// Token that stops the workers of this job when canceled
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel_token
+    }
+
+    /// Ask all workers to stop and block until they have
+    pub fn cancel(mut self) {
+        self.cancel_token.cancel();
+        self.join_workers();
+    }
+
+    /// Ask all workers to stop, block until they have and return every tile that was still waiting
+    pub fn stop(mut self) -> Vec<RenderedTile> {
+        self.cancel_token.cancel();
+        self.join_workers();
+        self.finished_tiles()
+    }
+
+    /// Take all tiles that were finished since the last call without blocking
+    pub fn finished_tiles(&mut self) -> Vec<RenderedTile> {
+        let tiles: Vec<_> = self.receiver.try_iter().collect();
+        self.received_tiles += tiles.len();
+        tiles
+    }
+
+    /// Fraction of the job that has been taken from it
+    /// An unbounded progressive render never makes progress
+    pub fn progress(&self) -> f32 {
+        if self.total_tiles == 0 {
+            return 1.0;
+        }
+
+        let tile_progress = self.received_tiles as f32 / self.total_tiles as f32;
+
+        match self.mode {
+            RenderMode::Full => tile_progress,
+            RenderMode::Progressive {
+                max_samples,
+                time_budget,
+            } => {
+                let sample_progress = max_samples.map_or(0.0, |max| tile_progress / max as f32);
+                let time_progress = time_budget.map_or(0.0, |budget| {
+                    self.started.elapsed().as_secs_f32() / budget.as_secs_f32()
+                });
+
+                sample_progress.max(time_progress).min(1.0)
+            }
+        }
+    }
+
+    /// Number of passes over the full viewport that have been taken from the job
+    pub fn completed_passes(&self) -> u32 {
+        match self.mode {
+            RenderMode::Full => (self.received_tiles == self.total_tiles) as u32,
+            RenderMode::Progressive { .. } => {
+                (self.received_tiles / self.total_tiles.max(1)) as u32
+            }
+        }
+    }
+
+    /// Returns true once every worker has run out of tiles
+    pub fn is_finished(&self) -> bool {
+        self.workers.iter().all(|worker| worker.is_finished())
+    }
+
+    /// Block until all workers have stopped
+    pub fn join(mut self) {
+        self.join_workers();
+    }
+
+    fn join_workers(&mut self) {
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                panic!("A render worker panicked.");
+            }
+        }
+    }
+}
+
+impl TileQueue {
+    fn work(&self, sender: Sender<RenderedTile>) {
+        while !self.cancel_token.is_canceled() {
+            let index = self.next_tile.fetch_add(1, Ordering::Relaxed);
+            let Some((tile, samples)) = self.work_item(index) else {
+                return;
+            };
+
+            let sample_count = samples.len() as u32;
+            let Some((colors, aovs)) = self.render_tile(tile, samples) else {
+                return;
+            };
+
+            let rendered = RenderedTile {
+                tile,
+                colors,
+                aovs,
+                sample_count,
+            };
+
+            if sender.send(rendered).is_err() {
+                // The tracer dropped the job, nobody is interested in the result anymore
+                return;
+            }
+        }
+    }
+
+    /// The tile and the range of sample numbers to render for the given position in the queue
+    /// Returns None once the queue is exhausted
+    fn work_item(&self, index: usize) -> Option<(Tile, Range<u32>)> {
+        match self.settings.render_mode {
+            RenderMode::Full => {
+                let tile = *self.tiles.get(index)?;
+                Some((tile, 0..self.settings.number_of_samples))
+            }
+            RenderMode::Progressive {
+                max_samples,
+                time_budget,
+            } => {
+                if self.tiles.is_empty() {
+                    return None;
+                }
+
+                let pass = (index / self.tiles.len()) as u32;
+
+                if max_samples.is_some_and(|max| pass >= max) {
+                    return None;
+                }
+
+                if time_budget.is_some_and(|budget| self.started.elapsed() >= budget) {
+                    return None;
+                }
+
+                let tile = self.tiles[index % self.tiles.len()];
+                Some((tile, pass..pass + 1))
+            }
+        }
+    }
+
+    /// Render all pixels of the tile and sum the given samples per pixel
+    /// Returns the colors and the AOV sums, or None if the job was canceled in the meantime
+    fn render_tile(&self, tile: Tile, samples: Range<u32>) -> Option<(Vec<Color>, Vec<f32>)> {
+        let width = self.settings.resolution.width;
+        let height = self.settings.resolution.height;
+        let scene = &self.scene;
+        let aovs = &self.settings.aovs;
+        let aov_channels = traced_channel_count(aovs);
+
+        let mut colors = Vec::with_capacity((tile.width * tile.height) as usize);
+        let mut aov_sums = vec![0.0; colors.capacity() * aov_channels];
+
+        for (index, (i, j)) in tile.pixels().enumerate() {
+            if self.cancel_token.is_canceled() {
+                return None;
+            }
+
+            let pixel_index = j * width + i;
+            let mut color = BLACK;
+            let pixel_aovs = &mut aov_sums[index * aov_channels..(index + 1) * aov_channels];
+
+            for s in samples.clone() {
+                // Dimensions 0 and 1 place the sample in the pixel, 2 and 3 on the lens
+                let mut sampler = SampleStream::new(self.settings.sampler.as_ref(), pixel_index, s);
+                let sample = sampler.next_vec2() - 0.5;
+
+                let h = (i as f32 + sample.x) / (width - 1) as f32;
+                let v = 1.0 - ((j as f32 + sample.y) / (height - 1) as f32);
+                let ray = scene.camera.get_lens_ray(h, v, sampler.next_vec2());
+
+                color += self.settings.integrator.radiance(
+                    scene,
+                    &ray,
+                    self.settings.max_depth,
+                    &mut sampler,
+                );
+
+                if aov_channels > 0 {
+                    trace_aovs(scene, &ray, aovs, s == samples.start, pixel_aovs);
+                }
+            }
+
+            colors.push(color);
+        }
+
+        Some((colors, aov_sums))
+    }
+}
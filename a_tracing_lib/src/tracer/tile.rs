@@ -0,0 +1,90 @@
+/// Width and height in pixels of the tiles a full render is split into
+pub const TILE_SIZE: u32 = 32;
+
+/// Rectangular region of the viewport that is rendered as a single unit of work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Iterate over the (x, y) coordinates of all pixels in the tile in row-major order
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let tile = *self;
+        (tile.y..tile.y + tile.height)
+            .flat_map(move |j| (tile.x..tile.x + tile.width).map(move |i| (i, j)))
+    }
+}
+
+/// Split a viewport of the given size into tiles of at most tile_size by tile_size pixels
+/// Tiles on the right and bottom edge are cropped to fit inside the viewport
+pub fn split_into_tiles(width: u32, height: u32, tile_size: u32) -> Vec<Tile> {
+    assert!(tile_size > 0);
+
+    let mut tiles = Vec::new();
+
+    for y in (0..height).step_by(tile_size as usize) {
+        for x in (0..width).step_by(tile_size as usize) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            });
+        }
+    }
+
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_exact() {
+        let tiles = split_into_tiles(64, 32, 32);
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(
+            tiles[1],
+            Tile {
+                x: 32,
+                y: 0,
+                width: 32,
+                height: 32
+            }
+        );
+    }
+
+    #[test]
+    fn test_split_cropped() {
+        let tiles = split_into_tiles(70, 40, 32);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles[5],
+            Tile {
+                x: 64,
+                y: 32,
+                width: 6,
+                height: 8
+            }
+        );
+    }
+
+    #[test]
+    fn test_split_covers_every_pixel_once() {
+        let (width, height) = (45, 77);
+        let mut covered = vec![0; (width * height) as usize];
+
+        for tile in split_into_tiles(width, height, 16) {
+            for (i, j) in tile.pixels() {
+                covered[(j * width + i) as usize] += 1;
+            }
+        }
+
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+}
@@ -1,4 +1,19 @@
-use a_tracing_lib::tracer::ATracer;
+use std::sync::Arc;
+
+use a_tracing_lib::{
+    color::display::ToneMapping,
+    integrator::{
+        AmbientOcclusionIntegrator, DebugIntegrator, DebugMode, Integrator, PathTracer,
+        WhittedIntegrator,
+    },
+    sampling::{
+        HaltonSampler, JitteredSampler, MultiJitteredSampler, RandomSampler, RegularSampler,
+        SampleGenerator, SobolSampler,
+    },
+    scene::background::{Background, PreethamSky},
+    tracer::{aov::Aov, ATracer, RenderMode, RenderStatus},
+    Vec3,
+};
 use egui::{ClippedPrimitive, Context, TexturesDelta};
 use egui_wgpu::{renderer::ScreenDescriptor, wgpu, Renderer};
 use pixels::PixelsContext;
@@ -16,7 +31,99 @@ pub(crate) struct GuiFramework {
 }
 
 /// Application state
-pub(crate) struct GuiState {}
+pub(crate) struct GuiState {
+    integrator: IntegratorChoice,
+    sampler: SamplerChoice,
+}
+
+/// The integrators that can be selected in the ui
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntegratorChoice {
+    PathTracer,
+    Whitted,
+    AmbientOcclusion,
+    Normals,
+    Albedo,
+}
+
+impl IntegratorChoice {
+    const ALL: [IntegratorChoice; 5] = [
+        IntegratorChoice::PathTracer,
+        IntegratorChoice::Whitted,
+        IntegratorChoice::AmbientOcclusion,
+        IntegratorChoice::Normals,
+        IntegratorChoice::Albedo,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            IntegratorChoice::PathTracer => "Path tracer",
+            IntegratorChoice::Whitted => "Direct light",
+            IntegratorChoice::AmbientOcclusion => "Ambient occlusion",
+            IntegratorChoice::Normals => "Normals",
+            IntegratorChoice::Albedo => "Albedo",
+        }
+    }
+
+    fn integrator(self) -> Arc<dyn Integrator> {
+        match self {
+            IntegratorChoice::PathTracer => Arc::new(PathTracer),
+            IntegratorChoice::Whitted => Arc::new(WhittedIntegrator),
+            IntegratorChoice::AmbientOcclusion => Arc::new(AmbientOcclusionIntegrator::default()),
+            IntegratorChoice::Normals => Arc::new(DebugIntegrator::new(DebugMode::Normals)),
+            IntegratorChoice::Albedo => Arc::new(DebugIntegrator::new(DebugMode::Albedo)),
+        }
+    }
+}
+
+/// The samplers that can be selected in the ui
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SamplerChoice {
+    Regular,
+    Random,
+    Jittered,
+    MultiJittered,
+    Halton,
+    Sobol,
+}
+
+impl SamplerChoice {
+    const ALL: [SamplerChoice; 6] = [
+        SamplerChoice::Regular,
+        SamplerChoice::Random,
+        SamplerChoice::Jittered,
+        SamplerChoice::MultiJittered,
+        SamplerChoice::Halton,
+        SamplerChoice::Sobol,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SamplerChoice::Regular => "Regular",
+            SamplerChoice::Random => "Random",
+            SamplerChoice::Jittered => "Jittered",
+            SamplerChoice::MultiJittered => "Multi-jittered",
+            SamplerChoice::Halton => "Halton",
+            SamplerChoice::Sobol => "Sobol",
+        }
+    }
+
+    /// The grid based samplers get enough cells per side to fit the number of samples
+    fn sampler(self, number_of_samples: u32) -> Arc<dyn SampleGenerator> {
+        let samples_per_side = ((number_of_samples as f32).sqrt().ceil() as u32).max(1);
+
+        match self {
+            SamplerChoice::Regular => Arc::new(RegularSampler::new(samples_per_side)),
+            SamplerChoice::Random => Arc::new(RandomSampler::new(0)),
+            SamplerChoice::Jittered => Arc::new(JitteredSampler::new(samples_per_side, 0)),
+            SamplerChoice::MultiJittered => {
+                Arc::new(MultiJitteredSampler::new(samples_per_side, 0))
+            }
+            SamplerChoice::Halton => Arc::new(HaltonSampler::new(0)),
+            SamplerChoice::Sobol => Arc::new(SobolSampler::new(0)),
+        }
+    }
+}
 
 impl GuiFramework {
     /// Create ui
@@ -131,7 +238,10 @@ impl GuiFramework {
 
 impl GuiState {
     fn new() -> Self {
-        Self {}
+        Self {
+            integrator: IntegratorChoice::PathTracer,
+            sampler: SamplerChoice::Regular,
+        }
     }
 
     fn ui(&mut self, ctx: &Context, tracer: &mut ATracer) {
@@ -139,6 +249,219 @@ impl GuiState {
             if ui.add(egui::Button::new("Render")).clicked() {
                 tracer.start_render();
             }
+
+            let mut progressive = matches!(
+                tracer.render_settings().render_mode,
+                RenderMode::Progressive { .. }
+            );
+            if ui.checkbox(&mut progressive, "Progressive").changed() {
+                tracer.render_settings_mut().render_mode = if progressive {
+                    RenderMode::Progressive {
+                        max_samples: None,
+                        time_budget: None,
+                    }
+                } else {
+                    RenderMode::Full
+                };
+            }
+
+            let previous_integrator = self.integrator;
+            egui::ComboBox::from_label("Integrator")
+                .selected_text(self.integrator.name())
+                .show_ui(ui, |ui| {
+                    for choice in IntegratorChoice::ALL {
+                        ui.selectable_value(&mut self.integrator, choice, choice.name());
+                    }
+                });
+            if self.integrator != previous_integrator {
+                tracer.render_settings_mut().integrator = self.integrator.integrator();
+            }
+
+            let previous_sampler = self.sampler;
+            let mut number_of_samples = tracer.render_settings().number_of_samples;
+            let samples_changed = ui
+                .add(
+                    egui::Slider::new(&mut number_of_samples, 1..=1024)
+                        .logarithmic(true)
+                        .text("Samples"),
+                )
+                .changed();
+            egui::ComboBox::from_label("Sampler")
+                .selected_text(self.sampler.name())
+                .show_ui(ui, |ui| {
+                    for choice in SamplerChoice::ALL {
+                        ui.selectable_value(&mut self.sampler, choice, choice.name());
+                    }
+                });
+            if samples_changed || self.sampler != previous_sampler {
+                let settings = tracer.render_settings_mut();
+                settings.number_of_samples = number_of_samples;
+                settings.sampler = self.sampler.sampler(number_of_samples);
+            }
+
+            let camera = &tracer.get_scene().camera;
+            let mut aperture_radius = camera.aperture_radius();
+            let mut focus_distance = camera.focus_distance();
+            let aperture_changed = ui
+                .add(egui::Slider::new(&mut aperture_radius, 0.0..=1.0).text("Aperture radius"))
+                .changed();
+            let focus_changed = ui
+                .add(
+                    egui::Slider::new(&mut focus_distance, 0.1..=100.0)
+                        .logarithmic(true)
+                        .text("Focus distance"),
+                )
+                .changed();
+            if aperture_changed || focus_changed {
+                tracer
+                    .get_scene_mut()
+                    .camera
+                    .set_lens(aperture_radius, focus_distance);
+            }
+
+            let background = &tracer.get_scene().background;
+            let background_name = match background {
+                Background::Color(_) => "color",
+                Background::Gradient { .. } => "gradient",
+                Background::Environment(_) => "environment",
+                Background::Sky(_) => "sky",
+            };
+            egui::ComboBox::from_label("Background")
+                .selected_text(background_name)
+                .show_ui(ui, |ui| {
+                    if ui
+                        .selectable_label(background_name == "gradient", "gradient")
+                        .clicked()
+                        && background_name != "gradient"
+                    {
+                        tracer.get_scene_mut().background = Background::default();
+                    }
+                    if ui
+                        .selectable_label(background_name == "sky", "sky")
+                        .clicked()
+                        && background_name != "sky"
+                    {
+                        tracer.get_scene_mut().background =
+                            Background::Sky(PreethamSky::new(Vec3::new(0.0, 1.0, 1.0), 3.0));
+                    }
+                });
+
+            if let Background::Sky(sky) = &tracer.get_scene().background {
+                let sun = sky.sun_direction();
+                let mut elevation = sun.y.asin().to_degrees();
+                let mut azimuth = sun.x.atan2(sun.z).to_degrees();
+                let mut turbidity = sky.turbidity();
+                let sun_changed = ui
+                    .add(egui::Slider::new(&mut elevation, -10.0..=90.0).text("Sun elevation"))
+                    .changed()
+                    | ui.add(egui::Slider::new(&mut azimuth, -180.0..=180.0).text("Sun azimuth"))
+                        .changed();
+                let turbidity_changed = ui
+                    .add(egui::Slider::new(&mut turbidity, 1.7..=10.0).text("Turbidity"))
+                    .changed();
+                if sun_changed || turbidity_changed {
+                    if let Background::Sky(sky) = &mut tracer.get_scene_mut().background {
+                        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+                        sky.set_sun_direction(Vec3::new(
+                            elevation.cos() * azimuth.sin(),
+                            elevation.sin(),
+                            elevation.cos() * azimuth.cos(),
+                        ));
+                        sky.set_turbidity(turbidity);
+                    }
+                }
+            }
+
+            if let Background::Environment(map) = &tracer.get_scene().background {
+                let mut rotation = map.rotation().to_degrees();
+                let mut intensity = map.intensity();
+                let rotation_changed = ui
+                    .add(
+                        egui::Slider::new(&mut rotation, -180.0..=180.0)
+                            .text("Environment rotation"),
+                    )
+                    .changed();
+                let intensity_changed = ui
+                    .add(
+                        egui::Slider::new(&mut intensity, 0.0..=16.0)
+                            .logarithmic(true)
+                            .text("Environment intensity"),
+                    )
+                    .changed();
+                if rotation_changed || intensity_changed {
+                    if let Background::Environment(map) = &mut tracer.get_scene_mut().background {
+                        map.set_rotation(rotation.to_radians());
+                        map.set_intensity(intensity);
+                    }
+                }
+            }
+
+            let mut max_depth = tracer.render_settings().max_depth;
+            if ui
+                .add(egui::Slider::new(&mut max_depth, 1..=16).text("Tracing depth"))
+                .changed()
+            {
+                tracer.render_settings_mut().max_depth = max_depth;
+            }
+
+            ui.collapsing("Outputs", |ui| {
+                for aov in Aov::ALL {
+                    let mut enabled = tracer.render_settings().aovs.contains(&aov);
+                    if ui.checkbox(&mut enabled, aov.name()).changed() {
+                        let aovs = &mut tracer.render_settings_mut().aovs;
+                        aovs.retain(|&enabled_aov| enabled_aov != aov);
+                        if enabled {
+                            aovs.push(aov);
+                        }
+                    }
+                }
+            });
+
+            let mut displayed_aov = tracer.displayed_aov();
+            egui::ComboBox::from_label("View")
+                .selected_text(displayed_aov.map_or("color", Aov::name))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut displayed_aov, None, "color");
+                    for &aov in &tracer.render_settings().aovs {
+                        ui.selectable_value(&mut displayed_aov, Some(aov), aov.name());
+                    }
+                });
+            if displayed_aov != tracer.displayed_aov() {
+                tracer.set_displayed_aov(displayed_aov);
+            }
+
+            let mut display_transform = tracer.display_transform();
+            ui.add(egui::Slider::new(&mut display_transform.exposure, -8.0..=8.0).text("Exposure"));
+            egui::ComboBox::from_label("Tone mapping")
+                .selected_text(display_transform.tone_mapping.name())
+                .show_ui(ui, |ui| {
+                    for tone_mapping in ToneMapping::ALL {
+                        ui.selectable_value(
+                            &mut display_transform.tone_mapping,
+                            tone_mapping,
+                            tone_mapping.name(),
+                        );
+                    }
+                });
+            if display_transform != tracer.display_transform() {
+                tracer.set_display_transform(display_transform);
+            }
+
+            if tracer.render_status() == RenderStatus::Rendering {
+                if progressive {
+                    ui.label(format!("Samples: {}", tracer.completed_passes()));
+
+                    if ui.add(egui::Button::new("Stop")).clicked() {
+                        tracer.stop_render();
+                    }
+                } else {
+                    ui.add(egui::ProgressBar::new(tracer.render_progress()).show_percentage());
+                }
+
+                if ui.add(egui::Button::new("Cancel")).clicked() {
+                    tracer.cancel_render();
+                }
+            }
         });
     }
 }
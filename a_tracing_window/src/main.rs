@@ -48,6 +48,16 @@ fn main() -> Result<(), Error> {
 
     let mut tracer = ATracer::new(WIDTH, HEIGHT);
 
+    // An optional scene file can be passed as the first argument
+    if let Some(path) = std::env::args_os().nth(1) {
+        if let Err(err) = tracer.load_scene(&path) {
+            error!("Loading scene failed: {err}");
+        }
+
+        // The buffers have to keep matching the window, not the resolution stored in the file
+        tracer.resize(WIDTH, HEIGHT);
+    }
+
     event_loop.run(move |event, _, control_flow| {
         if input.update(&event) {
             if input.key_pressed(VirtualKeyCode::Escape) || input.close_requested() {